@@ -0,0 +1,502 @@
+use std::fmt::Write;
+
+use crate::{
+    ir::{Ir, Op},
+    vm::Options,
+    CellBits, ConfigError, CellOverflow, Eof, Overflow,
+};
+
+/// 把 `Ir` 翻译成 x86-64 Linux 的 GNU 汇编，不依赖 libc
+///
+/// `as prog.s -o prog.o && ld prog.o -o prog` 即可得到静态可执行文件。
+/// 寄存器约定：rbx 为纸带首地址，r12 为实际下标，r13 为逻辑位置，r14 为输出缓冲区已用长度。
+/// 格子按 `cell_bits` 占 1、2、4 或 8 个字节。
+/// 越界或读写失败时在 stderr 打印原因并以 4 退出，越界只报指令下标和指针位置，不像解释器那样给出行列。
+pub fn compile(ir: &Ir, options: Options) -> Result<String, ConfigError> {
+    let options = options.fixed()?;
+    let len = options.tape_length.get();
+    let cell = Cell::new(options.cell_bits);
+    let wraps = matches!(options.cell_overflow, CellOverflow::Wrap);
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "\
+    .intel_syntax noprefix
+    .globl _start
+
+    .bss
+tape:
+    .zero {}
+outbuf:
+    .zero 4096
+numbuf:
+    .zero 32
+
+    .section .rodata
+overflow_message:
+    .ascii \"Overflow exit. (command \"
+cell_message:
+    .ascii \"Cell overflow exit. (command \"
+minus_message:
+    .ascii \"-\"
+pointer_message:
+    .ascii \", pointer \"
+end_message:
+    .ascii \")\\n\"
+input_message:
+    .ascii \"Failed to read input.\\n\"
+output_message:
+    .ascii \"Failed to write output.\\n\"
+
+    .text
+_start:
+    lea rbx, [rip + tape]
+    xor r12d, r12d
+    xor r13d, r13d
+    xor r14d, r14d
+",
+        len * cell.size
+    );
+
+    for (index, op) in ir.ops().iter().enumerate() {
+        match op {
+            Op::Add(delta) if !wraps => {
+                let start = ir.span(index).map_or(0, |span| span.start);
+                let _ = writeln!(out, "    mov rax, {delta}\n    mov rsi, {start}\n    call add_cell");
+            },
+            Op::Add(delta) => {
+                let amount = delta.unsigned_abs() & cell.max;
+                if amount > i32::MAX as u64 {
+                    let _ = writeln!(out, "    mov rax, {delta}\n    add {}, {}", cell.at(0), cell.register("a"));
+                } else {
+                    let instruction = if *delta < 0 { "sub" } else { "add" };
+                    let _ = writeln!(out, "    {instruction} {}, {amount}", cell.at(0));
+                }
+            },
+            Op::Move(offset) => {
+                let start = ir.span(index).map_or(0, |span| span.start);
+                let _ = write!(
+                    out,
+                    "\
+    lea rax, [r13 {}]
+    cmp rax, {len}
+    jae .L{index}_slow
+    mov r13, rax
+    mov r12, rax
+    jmp .L{index}_done
+.L{index}_slow:
+    mov rsi, {start}
+    call shift
+.L{index}_done:
+",
+                    signed(*offset)
+                );
+            },
+            // 先读到 numbuf，输入结束时按 `Eof` 处理
+            Op::In => {
+                let eof = match options.eof {
+                    Eof::Unchanged => format!("    jz .L{index}_eof\n    movzx eax, byte ptr [rip + numbuf]\n"),
+                    Eof::Zero | Eof::Max => format!(
+                        "    movzx eax, byte ptr [rip + numbuf]\n    jnz .L{index}_read\n    mov rax, {}\n.L{index}_read:\n",
+                        if let Eof::Zero = options.eof { 0 } else { -1 }
+                    ),
+                };
+                let _ = write!(
+                    out,
+                    "\
+    call flush
+    xor eax, eax
+    xor edi, edi
+    lea rsi, [rip + numbuf]
+    mov edx, 1
+    syscall
+    test rax, rax
+    js input_error
+{eof}    mov {}, {}
+.L{index}_eof:
+",
+                    cell.at(0),
+                    cell.register("a")
+                );
+            },
+            Op::Out => {
+                let routine = if options.output_as_int { "put_int" } else { "put_byte" };
+                let _ = writeln!(out, "    {}\n    call {routine}", cell.load());
+            },
+            Op::Open(close) => {
+                let _ = writeln!(out, "    cmp {}, 0\n    je .L{close}_end\n.L{index}_body:", cell.at(0));
+            },
+            Op::Close(open) => {
+                let _ = writeln!(out, "    cmp {}, 0\n    jne .L{open}_body\n.L{index}_end:", cell.at(0));
+            },
+            Op::Clear => {
+                let _ = writeln!(out, "    mov {}, 0", cell.at(0));
+            },
+            Op::Scan { step, min, max } => {
+                let _ = writeln!(out, ".L{index}_scan:\n    cmp {}, 0\n    je .L{index}_done", cell.at(0));
+                fits(&mut out, index, *min, *max, len);
+                let _ = writeln!(
+                    out,
+                    "    add r12, {step}\n    mov r13, r12\n    jmp .L{index}_scan\n.L{index}_done:"
+                );
+            },
+            Op::MulAdd { .. } if !wraps => (),
+            Op::MulAdd { targets, min, max } => {
+                fits(&mut out, index, *min, *max, len);
+                let _ = writeln!(out, "    {}", cell.load());
+                for (offset, factor) in targets {
+                    let _ = writeln!(
+                        out,
+                        "    imul rcx, rax, {factor}\n    add {}, {}",
+                        cell.at(*offset),
+                        cell.register("c")
+                    );
+                }
+                let _ = writeln!(out, "    mov {}, 0\n.L{index}_done:", cell.at(0));
+            },
+        }
+    }
+
+    out.push_str(
+        "\
+    call flush
+    mov eax, 60
+    xor edi, edi
+    syscall
+",
+    );
+    out.push_str(&shift_routine(options.overflow, len));
+    if !wraps {
+        out.push_str(&add_routine(options.cell_overflow, &cell));
+    }
+    out.push_str(RUNTIME);
+    Ok(out)
+}
+
+/// 按格子宽度生成内存操作数和寄存器名
+struct Cell {
+    size: usize,
+    max: u64,
+}
+
+impl Cell {
+    fn new(cell_bits: CellBits) -> Cell {
+        Cell {
+            size: cell_bits.bits() as usize / 8,
+            max: cell_bits.max(),
+        }
+    }
+
+    /// 相对当前格子偏移 `offset` 格的内存操作数
+    fn at(&self, offset: i64) -> String {
+        let width = match self.size {
+            1 => "byte",
+            2 => "word",
+            4 => "dword",
+            _ => "qword",
+        };
+        let scale = if self.size == 1 { String::new() } else { format!("*{}", self.size) };
+        let offset = if offset == 0 { String::new() } else { format!(" {}", signed(offset * self.size as i64)) };
+        format!("{width} ptr [rbx + r12{scale}{offset}]")
+    }
+
+    /// 与格子同宽的 rax、rcx 等寄存器，`name` 为 "a"、"c" 等
+    fn register(&self, name: &str) -> String {
+        match self.size {
+            1 => format!("{name}l"),
+            2 => format!("{name}x"),
+            4 => format!("e{name}x"),
+            _ => format!("r{name}x"),
+        }
+    }
+
+    /// 把当前格子的值零扩展到 rax
+    fn load(&self) -> String {
+        match self.size {
+            1 | 2 => format!("movzx eax, {}", self.at(0)),
+            4 => format!("mov eax, {}", self.at(0)),
+            _ => format!("mov rax, {}", self.at(0)),
+        }
+    }
+}
+
+fn signed(value: i64) -> String {
+    if value < 0 {
+        format!("- {}", -value)
+    } else {
+        format!("+ {}", value)
+    }
+}
+
+/// 指针未越界且 `r12 + min..=r12 + max` 都在纸带内，否则跳到 `.L{index}_done`
+fn fits(out: &mut String, index: usize, min: i64, max: i64, len: usize) {
+    let _ = write!(
+        out,
+        "\
+    cmp r13, r12
+    jne .L{index}_done
+    lea rax, [r12 {}]
+    test rax, rax
+    js .L{index}_done
+    lea rax, [r12 {}]
+    cmp rax, {len}
+    jge .L{index}_done
+",
+        signed(min),
+        signed(max)
+    );
+}
+
+/// 越界时的慢速路径，rax 为新的逻辑位置，rsi 为第一条 `>`/`<` 的下标
+fn shift_routine(overflow: Overflow, len: usize) -> String {
+    let last = len - 1;
+    let body = match overflow {
+        Overflow::Block => format!(
+            "\
+    xor ecx, ecx
+    test rax, rax
+    cmovs rax, rcx
+    mov rcx, {last}
+    cmp rax, rcx
+    cmovg rax, rcx
+    mov r13, rax
+    mov r12, rax
+    ret
+"
+        ),
+        Overflow::Overflow => format!(
+            "\
+    mov r13, rax
+    xor ecx, ecx
+    test rax, rax
+    cmovs rax, rcx
+    mov rcx, {last}
+    cmp rax, rcx
+    cmovg rax, rcx
+    mov r12, rax
+    ret
+"
+        ),
+        Overflow::Loop => format!(
+            "\
+    cqo
+    mov rcx, {len}
+    idiv rcx
+    lea rax, [rdx + rcx]
+    test rdx, rdx
+    cmovs rdx, rax
+    mov r13, rdx
+    mov r12, rdx
+    ret
+"
+        ),
+        Overflow::Exit | Overflow::Grow => format!(
+            "\
+    mov rcx, {last}
+    sub rcx, r13
+    mov rdx, r13
+    xor r8d, r8d
+    cmp rax, r13
+    cmovg rdx, rcx
+    mov rcx, {last}
+    cmovg r8, rcx
+    add rsi, rdx
+    push r8
+    push rsi
+    call flush
+    lea rsi, [rip + overflow_message]
+    mov edx, 24
+    call error_write
+    pop rax
+    call error_int
+    lea rsi, [rip + pointer_message]
+    mov edx, 10
+    call error_write
+    pop rax
+    call error_int
+    lea rsi, [rip + end_message]
+    mov edx, 2
+    call error_write
+    mov eax, 60
+    mov edi, 4
+    syscall
+"
+        ),
+    };
+    format!("\nshift:\n{body}")
+}
+
+/// 不回绕时加减当前格子的例程，rax 为增量，rsi 为第一条 `+`/`-` 的下标
+fn add_routine(cell_overflow: CellOverflow, cell: &Cell) -> String {
+    // 越过上界时 rcx 为还能加的量，越过下界时 rax 为还能减的量
+    let (over, under) = match cell_overflow {
+        CellOverflow::Saturate => (
+            format!("    mov rax, {}\n    jmp .Ladd_store", cell.max),
+            "    xor eax, eax\n    jmp .Ladd_store".to_string(),
+        ),
+        _ => (
+            "    add rsi, rcx\n    jmp cell_error".to_string(),
+            "    add rsi, rax\n    jmp cell_error".to_string(),
+        ),
+    };
+    let mut out = format!(
+        "
+add_cell:
+    mov rdx, rax
+    {}
+    test rdx, rdx
+    js .Ladd_down
+    mov rcx, {}
+    sub rcx, rax
+    cmp rdx, rcx
+    ja .Ladd_over
+    add rax, rdx
+    jmp .Ladd_store
+.Ladd_down:
+    mov rcx, rdx
+    neg rcx
+    cmp rcx, rax
+    ja .Ladd_under
+    sub rax, rcx
+.Ladd_store:
+    mov {}, {}
+    ret
+.Ladd_over:
+{over}
+.Ladd_under:
+{under}
+",
+        cell.load(),
+        cell.max,
+        cell.at(0),
+        cell.register("a")
+    );
+    if let CellOverflow::Exit = cell_overflow {
+        // 报告逐次加减到越界的那一条指令，逻辑位置可能为负
+        out.push_str(
+            "
+cell_error:
+    push r13
+    push rsi
+    call flush
+    lea rsi, [rip + cell_message]
+    mov edx, 29
+    call error_write
+    pop rax
+    call error_int
+    lea rsi, [rip + pointer_message]
+    mov edx, 10
+    call error_write
+    pop rax
+    test rax, rax
+    jns .Lcell_pointer
+    push rax
+    lea rsi, [rip + minus_message]
+    mov edx, 1
+    call error_write
+    pop rax
+    neg rax
+.Lcell_pointer:
+    call error_int
+    lea rsi, [rip + end_message]
+    mov edx, 2
+    call error_write
+    mov eax, 60
+    mov edi, 4
+    syscall
+",
+        );
+    }
+    out
+}
+
+/// 输出缓冲和错误处理等运行时例程
+const RUNTIME: &str = "
+put_byte:
+    lea rcx, [rip + outbuf]
+    mov byte ptr [rcx + r14], al
+    inc r14
+    cmp r14, 4096
+    jae flush
+    ret
+
+put_int:
+    call format_int
+.Lput_digit:
+    mov al, byte ptr [rsi]
+    push rsi
+    push rdx
+    call put_byte
+    pop rdx
+    pop rsi
+    inc rsi
+    dec rdx
+    jnz .Lput_digit
+    mov al, 32
+    jmp put_byte
+
+# 把 rax 转成十进制放进 numbuf，返回 rsi 为首地址，rdx 为长度
+format_int:
+    lea rsi, [rip + numbuf + 32]
+    mov rcx, 10
+.Lformat_digit:
+    xor edx, edx
+    div rcx
+    add dl, 48
+    dec rsi
+    mov byte ptr [rsi], dl
+    test rax, rax
+    jnz .Lformat_digit
+    lea rdx, [rip + numbuf + 32]
+    sub rdx, rsi
+    ret
+
+flush:
+    xor esi, esi
+.Lflush_more:
+    cmp rsi, r14
+    jae .Lflush_done
+    push rsi
+    mov eax, 1
+    mov edi, 1
+    lea rcx, [rip + outbuf]
+    mov rdx, r14
+    sub rdx, rsi
+    add rsi, rcx
+    syscall
+    pop rsi
+    test rax, rax
+    js output_error
+    add rsi, rax
+    jmp .Lflush_more
+.Lflush_done:
+    xor r14d, r14d
+    ret
+
+error_write:
+    mov eax, 1
+    mov edi, 2
+    syscall
+    ret
+
+error_int:
+    call format_int
+    jmp error_write
+
+input_error:
+    call flush
+    lea rsi, [rip + input_message]
+    mov edx, 22
+    jmp error_exit
+
+output_error:
+    xor r14d, r14d
+    lea rsi, [rip + output_message]
+    mov edx, 24
+
+error_exit:
+    call error_write
+    mov eax, 60
+    mov edi, 4
+    syscall
+";
@@ -0,0 +1,198 @@
+use std::fmt::Write;
+
+use crate::{
+    ir::{Ir, Op},
+    vm::Options,
+    CellOverflow, ConfigError, Eof, Overflow,
+};
+
+/// 把 `Ir` 翻译成独立的 C 源码，纸带长度、格子位数、`Overflow`、`CellOverflow` 和 `output_as_int` 都在生成时确定
+///
+/// 越界时在 stderr 打印原因、出错指令的下标和指针位置并以 4 退出，生成的程序不带源码，所以报错里没有行号和列号。
+/// 纸带不会扩展，`Overflow::Grow` 直接分配 `tape_limit` 个格子，同时设置了 `grow_left` 时返回错误。
+pub fn compile(ir: &Ir, options: Options) -> Result<String, ConfigError> {
+    let options = options.fixed()?;
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "\
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#define LEN {}L
+
+static uint{}_t tape[LEN];
+static long p = 0;  /* 实际读写的格子 */
+static long vp = 0; /* 指针的逻辑位置 */
+
+",
+        options.tape_length,
+        options.cell_bits.bits()
+    );
+    out.push_str(shift_function(options.overflow));
+    let cell_max = options.cell_bits.max();
+    let wraps = matches!(options.cell_overflow, CellOverflow::Wrap);
+    let _ = write!(out, "\n#define MAX {cell_max}ULL\n");
+    if !wraps {
+        out.push_str(add_function(options.cell_overflow));
+    }
+    out.push_str(
+        "
+static int fits(long min, long max) {
+    return vp == p && p + min >= 0 && p + max < LEN;
+}
+
+int main(void) {
+",
+    );
+
+    let mut depth = 1;
+    for (index, op) in ir.ops().iter().enumerate() {
+        if let Op::Close(_) = op {
+            depth -= 1;
+        }
+        let indent = "    ".repeat(depth);
+        match op {
+            Op::Add(delta) if wraps => {
+                let sign = if *delta < 0 { '-' } else { '+' };
+                let _ = writeln!(out, "{indent}tape[p] {sign}= {};", delta.abs());
+            },
+            Op::Add(delta) => {
+                let start = ir.span(index).map_or(0, |span| span.start);
+                let _ = writeln!(out, "{indent}add({delta}, {start});");
+            },
+            Op::Move(offset) => {
+                let start = ir.span(index).map_or(0, |span| span.start);
+                let _ = writeln!(out, "{indent}shift({offset}, {start});");
+            },
+            Op::In => {
+                let _ = writeln!(out, "{indent}fflush(stdout);");
+                let read = match options.eof {
+                    Eof::Unchanged => "if (c != EOF) tape[p] = c;",
+                    Eof::Zero => "tape[p] = c == EOF ? 0 : c;",
+                    Eof::Max => "tape[p] = c == EOF ? MAX : c;",
+                };
+                let _ = writeln!(out, "{indent}{{ int c = getchar(); {read} }}");
+            },
+            Op::Out => {
+                if options.output_as_int {
+                    let _ = writeln!(out, "{indent}printf(\"%llu \", (unsigned long long)tape[p]);");
+                } else {
+                    let _ = writeln!(out, "{indent}putchar(tape[p]);");
+                }
+            },
+            Op::Open(_) => {
+                let _ = writeln!(out, "{indent}while (tape[p]) {{");
+                depth += 1;
+            },
+            Op::Close(_) => {
+                let _ = writeln!(out, "{indent}}}");
+            },
+            // 快速路径不适用时落到后面保留的 while 循环里
+            Op::Clear => {
+                let _ = writeln!(out, "{indent}tape[p] = 0;");
+            },
+            Op::Scan { step, min, max } => {
+                let _ = writeln!(
+                    out,
+                    "{indent}while (tape[p] && fits({min}, {max})) {{ p += {step}; vp = p; }}"
+                );
+            },
+            // 格子不回绕时乘法循环可能中途越界，只能逐条执行
+            Op::MulAdd { .. } if !wraps => (),
+            Op::MulAdd { targets, min, max } => {
+                let _ = writeln!(out, "{indent}if (fits({min}, {max})) {{");
+                for (offset, factor) in targets {
+                    let sign = if *offset < 0 { '-' } else { '+' };
+                    let _ = writeln!(
+                        out,
+                        "{indent}    tape[p {sign} {}] += tape[p] * {}ULL;",
+                        offset.abs(),
+                        *factor as u64 & cell_max
+                    );
+                }
+                let _ = writeln!(out, "{indent}    tape[p] = 0;");
+                let _ = writeln!(out, "{indent}}}");
+            },
+        }
+    }
+    out.push_str("    fflush(stdout);\n    return 0;\n}\n");
+    Ok(out)
+}
+
+/// 按 `CellOverflow` 生成加减当前格子的函数，`command` 为第一条 `+`/`-` 的下标，用于报错
+fn add_function(cell_overflow: CellOverflow) -> &'static str {
+    match cell_overflow {
+        CellOverflow::Wrap => "",
+        CellOverflow::Saturate => "\
+static void add(long long delta, long command) {
+    unsigned long long value = tape[p];
+    unsigned long long amount = delta < 0 ? 0ULL - delta : (unsigned long long)delta;
+    (void)command;
+    if (delta < 0)
+        tape[p] = amount > value ? 0 : value - amount;
+    else
+        tape[p] = amount > MAX - value ? MAX : value + amount;
+}
+",
+        CellOverflow::Exit => "\
+static void add(long long delta, long command) {
+    unsigned long long value = tape[p];
+    unsigned long long amount = delta < 0 ? 0ULL - delta : (unsigned long long)delta;
+    unsigned long long room = delta < 0 ? value : MAX - value;
+    if (amount > room) {
+        /* 逐次加减到越界的那一条指令 */
+        fflush(stdout);
+        fprintf(stderr, \"Cell overflow exit. (command %ld, pointer %ld)\\n\",
+                command + (long)room, vp);
+        exit(4);
+    }
+    tape[p] = delta < 0 ? value - amount : value + amount;
+}
+",
+    }
+}
+
+/// 按 `Overflow` 生成移动指针的函数，`command` 为第一条 `>`/`<` 的下标，用于报错
+fn shift_function(overflow: Overflow) -> &'static str {
+    match overflow {
+        Overflow::Block => "\
+static void shift(long offset, long command) {
+    (void)command;
+    vp += offset;
+    if (vp < 0) vp = 0;
+    if (vp >= LEN) vp = LEN - 1;
+    p = vp;
+}
+",
+        Overflow::Overflow => "\
+static void shift(long offset, long command) {
+    (void)command;
+    vp += offset;
+    p = vp < 0 ? 0 : vp >= LEN ? LEN - 1 : vp;
+}
+",
+        Overflow::Loop => "\
+static void shift(long offset, long command) {
+    (void)command;
+    vp = ((vp + offset) % LEN + LEN) % LEN;
+    p = vp;
+}
+",
+        Overflow::Exit | Overflow::Grow => "\
+static void shift(long offset, long command) {
+    long target = vp + offset;
+    if (target < 0 || target >= LEN) {
+        /* 逐格走到越界的那一条指令 */
+        long steps = offset > 0 ? LEN - 1 - vp : vp;
+        fflush(stdout);
+        fprintf(stderr, \"Overflow exit. (command %ld, pointer %ld)\\n\",
+                command + steps, offset > 0 ? LEN - 1 : 0L);
+        exit(4);
+    }
+    vp = p = target;
+}
+",
+    }
+}
@@ -0,0 +1,248 @@
+use std::{error, ffi::OsString, fmt, io};
+
+use crate::{
+    program::{char_width, Location},
+    KEY_VALUE_PAIRS,
+};
+
+/// 一个没有配对的括号
+#[derive(Debug)]
+pub struct Unmatched {
+    pub bracket: char,
+    pub location: Location,
+    source_line: String, // 括号所在的那一行，用于显示
+}
+
+impl Unmatched {
+    pub(crate) fn new(bracket: char, location: Location, source: &str) -> Unmatched {
+        let line = source.lines().nth(location.line - 1).unwrap_or("");
+        Unmatched {
+            bracket,
+            location,
+            source_line: String::from(line),
+        }
+    }
+}
+
+impl fmt::Display for Unmatched {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Location { line, column, .. } = self.location;
+        let number = line.to_string();
+        // 制表符原样保留，其余字符按在终端里占的宽度补空格，让 ^ 和括号对齐
+        let indent: String = self
+            .source_line
+            .chars()
+            .take(column - 1)
+            .map(|ch| if ch == '\t' { String::from("\t") } else { " ".repeat(char_width(ch)) })
+            .collect();
+        writeln!(
+            f,
+            "Unmatched '{}' at line {}, column {}:",
+            self.bracket, line, column
+        )?;
+        writeln!(f, "{} | {}", number, self.source_line)?;
+        write!(f, "{} | {}^", " ".repeat(number.len()), indent)
+    }
+}
+
+/// 源码中有括号无法配对，按出现顺序列出所有多余的括号
+#[derive(Debug)]
+pub struct ParseError {
+    pub unmatched: Vec<Unmatched>,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Brainfxxk source syntax error!")?;
+        for bracket in &self.unmatched {
+            write!(f, "\n{}", bracket)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for ParseError {}
+
+#[derive(Debug)]
+pub enum RuntimeErrorKind {
+    PointerOverflow, // Overflow::Exit 下指针越界
+    CellOverflow,    // CellOverflow::Exit 下格子的值越界
+    Input(io::Error),
+    Output(io::Error),
+}
+
+/// 执行某条指令时出错，记录出错时的位置
+#[derive(Debug)]
+pub struct RuntimeError {
+    pub kind: RuntimeErrorKind,
+    pub program_counter: usize,
+    pub pointer: i64, // 指针的逻辑位置
+    pub location: Location, // 指令在源码中的位置
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            RuntimeErrorKind::PointerOverflow => write!(f, "Overflow exit.")?,
+            RuntimeErrorKind::CellOverflow => write!(f, "Cell overflow exit.")?,
+            RuntimeErrorKind::Input(err) => write!(f, "Failed to read input. {}", err)?,
+            RuntimeErrorKind::Output(err) => write!(f, "Failed to write output. {}", err)?,
+        }
+        write!(
+            f,
+            " (command {} at line {}, column {}, pointer {})",
+            self.program_counter, self.location.line, self.location.column, self.pointer
+        )
+    }
+}
+
+impl error::Error for RuntimeError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match &self.kind {
+            RuntimeErrorKind::Input(err) | RuntimeErrorKind::Output(err) => Some(err),
+            RuntimeErrorKind::PointerOverflow | RuntimeErrorKind::CellOverflow => None,
+        }
+    }
+}
+
+/// 命令行参数有误，`Help` 表示用户请求查看帮助，`Unsupported` 表示参数本身没错但在当前模式下用不了
+#[derive(Debug)]
+pub enum ConfigError {
+    Help,
+    MissingPath,
+    NotUnicode(OsString),
+    Syntax(String),
+    UnknownKey(String),
+    InvalidValue { key: String, value: String },
+    Unsupported { key: String, reason: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Help => write!(f, "{}", KEY_VALUE_PAIRS),
+            ConfigError::MissingPath => write!(
+                f,
+                "\
+            Didn't find brainfxxk source file!\n\
+            Usage: brain_fxxker.exe [compile | asm | wat] <file_path> <key>=<value> <...>\n\
+            For more information, use brain_fxxker.exe help."
+            ),
+            ConfigError::NotUnicode(arg) => write!(
+                f,
+                "Parameters shouldn't contain not unicode characters in \"{:?}\"!",
+                arg
+            ),
+            ConfigError::Syntax(arg) => write!(
+                f,
+                "Parameter syntax error in \"{}\"! Wrong parameter syntax, please use <key>=<value>.",
+                arg
+            ),
+            ConfigError::UnknownKey(key) => {
+                write!(f, "Parameter syntax error! Given key \"{}\" doesn't exist.", key)
+            }
+            ConfigError::InvalidValue { key, value } => write!(
+                f,
+                "Parameter syntax error in \"{}={}\"! Wrong {} value.",
+                key, value, key
+            ),
+            ConfigError::Unsupported { key, reason } => {
+                write!(f, "Parameter \"{}\" can't be used here! It {}.", key, reason)
+            }
+        }
+    }
+}
+
+impl error::Error for ConfigError {}
+
+/// `run` 可能返回的所有错误
+#[derive(Debug)]
+pub enum Error {
+    Parse(ParseError),
+    Runtime(RuntimeError),
+    Config(ConfigError),
+    Io(io::Error), // 读取源文件或操作终端失败
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse(err) => err.fmt(f),
+            Error::Runtime(err) => err.fmt(f),
+            Error::Config(err) => err.fmt(f),
+            Error::Io(err) => err.fmt(f),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Parse(err) => Some(err),
+            Error::Runtime(err) => Some(err),
+            Error::Config(err) => Some(err),
+            Error::Io(err) => Some(err),
+        }
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(err: ParseError) -> Error {
+        Error::Parse(err)
+    }
+}
+
+impl From<RuntimeError> for Error {
+    fn from(err: RuntimeError) -> Error {
+        Error::Runtime(err)
+    }
+}
+
+impl From<ConfigError> for Error {
+    fn from(err: ConfigError) -> Error {
+        Error::Config(err)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Program;
+
+    /// 第一个没配对的括号的报错
+    fn report(source: &str) -> String {
+        match Program::from(source) {
+            Ok(_) => panic!("brackets should be unmatched"),
+            Err(err) => err.unmatched[0].to_string(),
+        }
+    }
+
+    #[test]
+    fn caret_under_the_bracket() {
+        assert_eq!(
+            report("+\n+-[+"),
+            "Unmatched '[' at line 2, column 3:\n2 | +-[+\n  |   ^"
+        );
+    }
+
+    #[test]
+    fn caret_after_double_width_text() {
+        assert_eq!(
+            report("注释[+"),
+            "Unmatched '[' at line 1, column 3:\n1 | 注释[+\n  |     ^"
+        );
+    }
+
+    #[test]
+    fn caret_after_tabs() {
+        assert_eq!(
+            report("\t+\t]"),
+            "Unmatched ']' at line 1, column 4:\n1 | \t+\t]\n  | \t \t^"
+        );
+    }
+}
@@ -0,0 +1,404 @@
+use std::ops::Range;
+
+use crate::{
+    error::{RuntimeError, RuntimeErrorKind},
+    program::{Key, Program},
+    tape::Tape,
+    vm::{self, Input, Options, Output},
+};
+
+/// 优化后的指令
+///
+/// `Clear`、`Scan`、`MulAdd` 后面紧跟着原样保留的循环，
+/// 快速路径不适用时（比如会碰到纸带边界，或格子不回绕）就退回去逐条执行这个循环。
+pub enum Op {
+    Add(i64),  // 连续的 + 或 -
+    Move(i64), // 连续的 > 或 <
+    In,
+    Out,
+    Open(usize),  // [，记录配对的 Close 的下标
+    Close(usize), // ]，记录配对的 Open 的下标
+    Clear,        // [-]
+    Scan { step: i64, min: i64, max: i64 }, // [>]、[<<] 等，min/max 为一轮中指针到过的范围
+    MulAdd { targets: Vec<(i64, i64)>, min: i64, max: i64 }, // [->+>++<<]，targets 为 (偏移, 倍数)
+}
+
+/// 由 `Program` 优化得到的中间表示，每个 `Op` 都能映射回原来的指令
+pub struct Ir {
+    program: Program,
+    ops: Vec<Op>,
+    spans: Vec<Range<usize>>, // 每个 Op 对应的 Program 指令下标范围
+}
+
+impl Ir {
+    pub fn new(program: Program) -> Ir {
+        let mut ops = Vec::new();
+        let mut spans = Vec::new();
+        let mut opens = Vec::new();
+        let mut index = 0;
+        while let Some(key) = program.get(index) {
+            let start = index;
+            index += 1;
+            let op = match *key {
+                Key::Add | Key::Sub => {
+                    index = run_end(&program, start);
+                    let count = (index - start) as i64;
+                    Op::Add(if let Key::Add = key { count } else { -count })
+                },
+                Key::Right | Key::Left => {
+                    index = run_end(&program, start);
+                    let count = (index - start) as i64;
+                    Op::Move(if let Key::Right = key { count } else { -count })
+                },
+                Key::In => Op::In,
+                Key::Out => Op::Out,
+                Key::If(back) => {
+                    if let Some(op) = pattern(&program, start, back) {
+                        ops.push(op);
+                        spans.push(start..back + 1);
+                    }
+                    opens.push(ops.len());
+                    Op::Open(0)
+                },
+                Key::Back(_) => {
+                    let open = opens.pop().expect("brackets are matched by Program");
+                    ops[open] = Op::Open(ops.len());
+                    Op::Close(open)
+                },
+                Key::Debug => continue,
+            };
+            ops.push(op);
+            spans.push(start..index);
+        }
+        Ir { program, ops, spans }
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    pub fn ops(&self) -> &[Op] {
+        &self.ops
+    }
+
+    /// 第 `index` 个 Op 对应的 `Program` 指令下标范围，可再经 `Program::location` 找到源码位置
+    pub fn span(&self, index: usize) -> Option<Range<usize>> {
+        self.spans.get(index).cloned()
+    }
+
+    /// 一口气执行到结束，返回最终的纸带
+    pub fn run<I, O>(&self, options: Options, input: &mut I, output: &mut O) -> Result<Tape, RuntimeError>
+    where
+        I: Input,
+        O: Output,
+    {
+        let mut tape = Tape::new(options);
+        let mut pc = 0;
+        while let Some(op) = self.ops.get(pc) {
+            match op {
+                Op::Add(delta) => {
+                    if tape.add(*delta).is_err() {
+                        return Err(self.add_error(pc, &mut tape));
+                    }
+                },
+                Op::Move(offset) => {
+                    if tape.shift(*offset).is_err() {
+                        return Err(self.move_error(pc, &mut tape));
+                    }
+                },
+                Op::In => {
+                    let max = tape.max();
+                    if let Err(err) = vm::read_cell(input, tape.current_mut(), options.eof, max) {
+                        return Err(self.error(RuntimeErrorKind::Input(err), pc, &tape));
+                    }
+                },
+                Op::Out => {
+                    if let Err(err) = vm::write_cell(output, tape.get(), options.output_as_int) {
+                        return Err(self.error(RuntimeErrorKind::Output(err), pc, &tape));
+                    }
+                },
+                Op::Open(close) => {
+                    if tape.get() == 0 {
+                        pc = *close;
+                    }
+                },
+                Op::Close(open) => {
+                    if tape.get() != 0 {
+                        pc = *open;
+                    }
+                },
+                Op::Clear => {
+                    tape.set(0);
+                    pc = self.loop_end(pc);
+                    continue;
+                },
+                Op::Scan { step, min, max } => {
+                    if scan(&mut tape, *step, *min, *max) {
+                        pc = self.loop_end(pc);
+                        continue;
+                    }
+                },
+                Op::MulAdd { targets, min, max } => {
+                    if mul_add(&mut tape, targets, *min, *max) {
+                        pc = self.loop_end(pc);
+                        continue;
+                    }
+                },
+            }
+            pc += 1;
+        }
+        Ok(tape)
+    }
+
+    /// 快速路径执行完后跳过后面保留的循环
+    fn loop_end(&self, pc: usize) -> usize {
+        match self.ops[pc + 1] {
+            Op::Open(close) => close + 1,
+            _ => unreachable!("a loop follows every pattern op"),
+        }
+    }
+
+    /// 合并过的加减越界时，逐次重做一遍，找出真正越界的那条指令
+    fn add_error(&self, pc: usize, tape: &mut Tape) -> RuntimeError {
+        let span = self.spans[pc].clone();
+        let delta = if let Op::Add(delta) = self.ops[pc] { delta.signum() } else { 0 };
+        for index in span.clone() {
+            if tape.add(delta).is_err() {
+                return self.error_at(RuntimeErrorKind::CellOverflow, index, tape);
+            }
+        }
+        self.error_at(RuntimeErrorKind::CellOverflow, span.start, tape)
+    }
+
+    /// 合并过的移动越界时，逐格重走一遍，找出真正越界的那条指令
+    pub(crate) fn move_error(&self, pc: usize, tape: &mut Tape) -> RuntimeError {
+        let span = self.spans[pc].clone();
+        let offset = if let Op::Move(offset) = self.ops[pc] { offset.signum() } else { 0 };
+        for index in span.clone() {
+            if tape.shift(offset).is_err() {
+                return self.error_at(RuntimeErrorKind::PointerOverflow, index, tape);
+            }
+        }
+        self.error_at(RuntimeErrorKind::PointerOverflow, span.start, tape)
+    }
+
+    pub(crate) fn error(&self, kind: RuntimeErrorKind, pc: usize, tape: &Tape) -> RuntimeError {
+        self.error_at(kind, self.spans[pc].start, tape)
+    }
+
+    fn error_at(&self, kind: RuntimeErrorKind, index: usize, tape: &Tape) -> RuntimeError {
+        RuntimeError {
+            kind,
+            program_counter: index,
+            pointer: tape.virtual_pointer(),
+            location: self.program.location(index).unwrap_or_default(),
+        }
+    }
+}
+
+/// 连续相同指令的结束下标
+fn run_end(program: &Program, start: usize) -> usize {
+    let same = |index| {
+        matches!(
+            (program.get(start), program.get(index)),
+            (Some(Key::Add), Some(Key::Add))
+                | (Some(Key::Sub), Some(Key::Sub))
+                | (Some(Key::Right), Some(Key::Right))
+                | (Some(Key::Left), Some(Key::Left))
+        )
+    };
+    let mut end = start + 1;
+    while same(end) {
+        end += 1;
+    }
+    end
+}
+
+/// 识别 `start..=back` 这个循环是否为清零、扫描或乘法循环
+fn pattern(program: &Program, start: usize, back: usize) -> Option<Op> {
+    let mut offset = 0;
+    let (mut min, mut max) = (0, 0);
+    let mut deltas: Vec<(i64, i64)> = Vec::new();
+    let mut arithmetic = 0; // 加减指令的个数
+    for index in start + 1..back {
+        match program.get(index)? {
+            Key::Right => offset += 1,
+            Key::Left => offset -= 1,
+            key @ (Key::Add | Key::Sub) => {
+                let delta = if let Key::Add = key { 1 } else { -1 };
+                arithmetic += 1;
+                match deltas.iter_mut().find(|(target, _)| *target == offset) {
+                    Some((_, sum)) => *sum += delta,
+                    None => deltas.push((offset, delta)),
+                }
+            },
+            _ => return None,
+        }
+        min = min.min(offset);
+        max = max.max(offset);
+    }
+    deltas.retain(|(_, delta)| *delta != 0);
+
+    // 相互抵消的加减在格子不回绕时仍可能越界，所以扫描循环里不能有加减，清零循环只能是 [-]
+    if offset != 0 {
+        return if arithmetic == 0 {
+            Some(Op::Scan { step: offset, min, max })
+        } else {
+            None
+        };
+    }
+    let counter = deltas.iter().position(|(target, _)| *target == 0)?;
+    if deltas.remove(counter).1 != -1 {
+        return None;
+    }
+    if arithmetic == 1 && min == 0 && max == 0 {
+        Some(Op::Clear)
+    } else {
+        Some(Op::MulAdd { targets: deltas, min, max })
+    }
+}
+
+/// 找到下一个为 0 的格子，途中会越界时停在越界前的那一轮并返回 `false`
+fn scan(tape: &mut Tape, step: i64, min: i64, max: i64) -> bool {
+    let mut pointer = tape.pointer();
+    let found = loop {
+        if tape.cells()[pointer] == 0 {
+            break true;
+        }
+        if !tape.fits(pointer, min, max) {
+            break false;
+        }
+        pointer = (pointer as i64 + step) as usize;
+    };
+    let offset = pointer as i64 - tape.pointer() as i64;
+    // 目标格子在纸带内，移动不会失败
+    let _ = tape.shift(offset);
+    found
+}
+
+fn mul_add(tape: &mut Tape, targets: &[(i64, i64)], min: i64, max: i64) -> bool {
+    let pointer = tape.pointer();
+    if !tape.wraps() || !tape.fits(pointer, min, max) {
+        return false;
+    }
+    let max = tape.max();
+    let cells = tape.cells_mut();
+    let count = cells[pointer];
+    for (offset, factor) in targets {
+        let cell = &mut cells[(pointer as i64 + offset) as usize];
+        *cell = cell.wrapping_add(count.wrapping_mul(*factor as u64)) & max;
+    }
+    cells[pointer] = 0;
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::VecDeque, num::NonZeroUsize};
+
+    use super::*;
+    use crate::{testing, vm::Vm, CellOverflow, Overflow, Step};
+
+    /// 源码为一个循环时，它前面生成的快速路径
+    fn first_op(source: &str) -> Op {
+        let ir = Ir::new(Program::from(source).unwrap());
+        let mut ops = ir.ops;
+        assert!(ops.len() > 1 && matches!(ops[1], Op::Open(_)), "{source} is not optimized");
+        ops.swap_remove(0)
+    }
+
+    fn optimized(source: &str) -> bool {
+        let ir = Ir::new(Program::from(source).unwrap());
+        !matches!(ir.ops[0], Op::Open(_))
+    }
+
+    #[test]
+    fn clear() {
+        assert!(matches!(first_op("[-]"), Op::Clear));
+    }
+
+    #[test]
+    fn scan() {
+        assert!(matches!(first_op("[>]"), Op::Scan { step: 1, min: 0, max: 1 }));
+        assert!(matches!(first_op("[<<]"), Op::Scan { step: -2, min: -2, max: 0 }));
+        assert!(matches!(first_op("[>><]"), Op::Scan { step: 1, min: 0, max: 2 }));
+    }
+
+    #[test]
+    fn mul_add() {
+        match first_op("[->+>++<<]") {
+            Op::MulAdd { targets, min, max } => {
+                assert_eq!(targets, [(1, 1), (2, 2)]);
+                assert_eq!((min, max), (0, 2));
+            },
+            _ => panic!("[->+>++<<] should be a multiplication loop"),
+        }
+        match first_op("[<--->-]") {
+            Op::MulAdd { targets, min, max } => {
+                assert_eq!(targets, [(-1, -3)]);
+                assert_eq!((min, max), (-1, 0));
+            },
+            _ => panic!("[<--->-] should be a multiplication loop"),
+        }
+    }
+
+    #[test]
+    fn not_optimized() {
+        // 加减相互抵消或计数器不是减一时不能一步算完
+        for source in ["[+-]", "[--]", "[+]", "[->+<+]", "[>+]", "[-.]", "[-[-]]", "[,]"] {
+            assert!(!optimized(source), "{source} should not be optimized");
+        }
+    }
+
+    /// 分别用 `Vm` 和 `Ir::run` 执行，比较输出、纸带、指针和错误，`Vm` 停不下来时跳过
+    fn compare(source: &str, options: Options) {
+        let program = Program::from(source).unwrap();
+        let mut vm = Vm::new(program, options, VecDeque::new(), Vec::new());
+        let expected = match vm.run_until(|vm| vm.command_executed() == 1000) {
+            Step::Next => return,
+            Step::Err(err) => Err((err.program_counter, err.pointer)),
+            Step::End => Ok(()),
+        };
+        let ir = Ir::new(Program::from(source).unwrap());
+        let mut output = Vec::new();
+        let tape = match ir.run(options, &mut VecDeque::new(), &mut output) {
+            Ok(tape) => {
+                assert!(expected.is_ok(), "{source}: Ir finished but Vm failed with {expected:?}");
+                tape
+            },
+            Err(err) => {
+                assert_eq!(expected, Err((err.program_counter, err.pointer)), "{source}");
+                return;
+            },
+        };
+        let (left, right) = (vm.tape(), &tape);
+        assert_eq!(vm.output(), &output, "{source}");
+        assert_eq!(left.cells(), right.cells(), "{source}");
+        assert_eq!(left.pointer(), right.pointer(), "{source}");
+        assert_eq!(left.virtual_pointer(), right.virtual_pointer(), "{source}");
+        assert_eq!(left.origin(), right.origin(), "{source}");
+    }
+
+    #[test]
+    fn same_as_vm_near_the_edges() {
+        let overflows = [Overflow::Block, Overflow::Overflow, Overflow::Loop, Overflow::Exit, Overflow::Grow];
+        let cell_overflows = [CellOverflow::Wrap, CellOverflow::Saturate, CellOverflow::Exit];
+        for overflow in overflows {
+            for cell_overflow in cell_overflows {
+                for grow_left in [false, true] {
+                    let options = Options {
+                        overflow,
+                        cell_overflow,
+                        tape_length: NonZeroUsize::new(4).unwrap(),
+                        tape_limit: NonZeroUsize::new(6).unwrap(),
+                        grow_left,
+                        ..Options::default()
+                    };
+                    for source in testing::edge_programs() {
+                        compare(&source, options);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,342 @@
+//! Linux x86-64 上的 JIT，把 `Ir` 翻译成机器码直接执行
+//!
+//! 寄存器约定：rbx 为纸带首地址，r12 为实际下标，r13 为逻辑位置，
+//! r14 指向 `State`，r15 为纸带长度。
+
+use std::{ffi::c_void, io, ptr};
+
+use crate::{
+    error::{RuntimeError, RuntimeErrorKind},
+    ir::{Ir, Op},
+    tape::{self, Tape},
+    vm::{self, Input, Options, Output},
+    CellBits, CellOverflow, Eof, Overflow,
+};
+
+/// 机器码与回调函数共享的状态，前四个字段的偏移写死在机器码里
+#[repr(C)]
+struct State<'a> {
+    pointer: usize,       // +0
+    virtual_pointer: i64, // +8
+    cells: *mut u8,       // +16
+    len: usize,           // +24
+    overflow: Overflow,
+    eof: Eof,
+    output_as_int: bool,
+    input: &'a mut dyn Input,
+    output: &'a mut dyn Output,
+    error: Option<RuntimeErrorKind>,
+}
+
+type Entry = unsafe extern "sysv64" fn(*mut State) -> u64;
+
+/// 编译好的机器码，放在可执行的内存页里
+pub struct Jit<'a> {
+    ir: &'a Ir,
+    options: Options,
+    memory: *mut c_void,
+    size: usize,
+}
+
+impl<'a> Jit<'a> {
+    /// 只支持 8 位且回绕的格子和固定长度的纸带，不支持或申请可执行内存失败时返回 `None`，调用者应退回解释器
+    pub fn compile(ir: &'a Ir, options: Options) -> Option<Jit<'a>> {
+        if !matches!(options.cell_bits, CellBits::B8)
+            || !matches!(options.cell_overflow, CellOverflow::Wrap)
+            || matches!(options.overflow, Overflow::Grow)
+        {
+            return None;
+        }
+        let code = Assembler::new().assemble(ir);
+        let size = code.len().div_ceil(PAGE_SIZE) * PAGE_SIZE;
+        // SAFETY: 申请新的匿名内存，写入后改为只读可执行，不与其他内存重叠
+        unsafe {
+            let memory = mmap(
+                ptr::null_mut(),
+                size,
+                PROT_READ | PROT_WRITE,
+                MAP_PRIVATE | MAP_ANONYMOUS,
+                -1,
+                0,
+            );
+            if memory == MAP_FAILED {
+                return None;
+            }
+            ptr::copy_nonoverlapping(code.as_ptr(), memory as *mut u8, code.len());
+            if mprotect(memory, size, PROT_READ | PROT_EXEC) != 0 {
+                munmap(memory, size);
+                return None;
+            }
+            Some(Jit { ir, options, memory, size })
+        }
+    }
+
+    /// 一口气执行到结束，返回最终的纸带
+    pub fn run<I, O>(&self, input: &mut I, output: &mut O) -> Result<Tape, RuntimeError>
+    where
+        I: Input,
+        O: Output,
+    {
+        let mut tape = Tape::new(self.options);
+        let mut cells = vec![0u8; self.options.tape_length.get()];
+        let mut state = State {
+            pointer: 0,
+            virtual_pointer: 0,
+            cells: cells.as_mut_ptr(),
+            len: cells.len(),
+            overflow: self.options.overflow,
+            eof: self.options.eof,
+            output_as_int: self.options.output_as_int,
+            input,
+            output,
+            error: None,
+        };
+        // SAFETY: 机器码由 `Assembler` 按 sysv64 约定生成，只访问 `State` 和纸带
+        let status = unsafe {
+            let entry: Entry = std::mem::transmute(self.memory);
+            entry(&mut state)
+        };
+        for (cell, &value) in tape.cells_mut().iter_mut().zip(&cells) {
+            *cell = value as u64;
+        }
+        tape.seek(state.pointer, state.virtual_pointer);
+        match (status, state.error) {
+            (0, _) => Ok(tape),
+            (pc, Some(RuntimeErrorKind::PointerOverflow)) => {
+                Err(self.ir.move_error(pc as usize - 1, &mut tape))
+            },
+            (pc, Some(kind)) => Err(self.ir.error(kind, pc as usize - 1, &tape)),
+            (_, None) => unreachable!("the JIT only fails after a callback reports an error"),
+        }
+    }
+}
+
+impl Drop for Jit<'_> {
+    fn drop(&mut self) {
+        // SAFETY: memory 由 compile 中的 mmap 得到，大小一致
+        unsafe {
+            munmap(self.memory, self.size);
+        }
+    }
+}
+
+/// 越界的慢速路径，按 `Overflow` 修正指针，`Exit` 越界时返回 1
+extern "sysv64" fn shift(state: &mut State, virtual_pointer: i64, offset: i64) -> u64 {
+    match tape::locate(virtual_pointer + offset, state.len, state.overflow) {
+        Some((virtual_pointer, pointer)) => {
+            state.virtual_pointer = virtual_pointer;
+            state.pointer = pointer;
+            0
+        },
+        None => {
+            state.error = Some(RuntimeErrorKind::PointerOverflow);
+            1
+        },
+    }
+}
+
+extern "sysv64" fn read(state: &mut State, cell: &mut u8) -> u64 {
+    let mut value = *cell as u64;
+    let result = vm::read_cell(state.input, &mut value, state.eof, u8::MAX as u64);
+    *cell = value as u8;
+    report(state, result, RuntimeErrorKind::Input)
+}
+
+extern "sysv64" fn write(state: &mut State, value: u8) -> u64 {
+    let result = vm::write_cell(state.output, value as u64, state.output_as_int);
+    report(state, result, RuntimeErrorKind::Output)
+}
+
+fn report(state: &mut State, result: io::Result<()>, kind: fn(io::Error) -> RuntimeErrorKind) -> u64 {
+    match result {
+        Ok(_) => 0,
+        Err(err) => {
+            state.error = Some(kind(err));
+            1
+        },
+    }
+}
+
+/// 生成机器码，跳转一律用 rel32
+struct Assembler {
+    code: Vec<u8>,
+    exits: Vec<usize>, // 需要回填为跳到结尾的 rel32 位置
+}
+
+impl Assembler {
+    fn new() -> Assembler {
+        Assembler { code: Vec::new(), exits: Vec::new() }
+    }
+
+    fn assemble(mut self, ir: &Ir) -> Vec<u8> {
+        self.prologue();
+        let mut opens = Vec::new();
+        for (index, op) in ir.ops().iter().enumerate() {
+            match op {
+                // add byte [rbx + r12], imm8
+                Op::Add(delta) => self.emit(&[0x42, 0x80, 0x04, 0x23, *delta as u8]),
+                Op::Move(offset) => self.shift(*offset, index),
+                Op::In => {
+                    self.emit(&[0x4C, 0x89, 0xF7]); // mov rdi, r14
+                    self.emit(&[0x4A, 0x8D, 0x34, 0x23]); // lea rsi, [rbx + r12]
+                    self.call(read as *const () as usize, index);
+                },
+                Op::Out => {
+                    self.emit(&[0x4C, 0x89, 0xF7]); // mov rdi, r14
+                    self.emit(&[0x42, 0x0F, 0xB6, 0x34, 0x23]); // movzx esi, byte [rbx + r12]
+                    self.call(write as *const () as usize, index);
+                },
+                Op::Open(_) => {
+                    self.emit(&[0x42, 0x80, 0x3C, 0x23, 0x00]); // cmp byte [rbx + r12], 0
+                    let jump = self.jump(&[0x0F, 0x84]); // je
+                    opens.push((jump, self.code.len()));
+                },
+                Op::Close(_) => {
+                    let (jump, body) = opens.pop().expect("brackets are matched by Program");
+                    self.emit(&[0x42, 0x80, 0x3C, 0x23, 0x00]); // cmp byte [rbx + r12], 0
+                    let back = self.jump(&[0x0F, 0x85]); // jne
+                    self.patch(back, body);
+                    self.patch(jump, self.code.len());
+                },
+                Op::Clear => self.emit(&[0x42, 0xC6, 0x04, 0x23, 0x00]), // mov byte [rbx + r12], 0
+                // `Scan` 和 `MulAdd` 可能越过纸带边界时跳过快速路径，落到后面保留的循环里逐条执行
+                Op::Scan { step, min, max } => {
+                    let top = self.code.len();
+                    self.emit(&[0x42, 0x80, 0x3C, 0x23, 0x00]); // cmp byte [rbx + r12], 0
+                    let found = self.jump(&[0x0F, 0x84]); // je
+                    let outside = self.fits(*min, *max);
+                    self.emit(&[0x49, 0x81, 0xC4]); // add r12, imm32
+                    self.imm32(*step);
+                    self.emit(&[0x4D, 0x89, 0xE5]); // mov r13, r12
+                    let again = self.jump(&[0xE9]); // jmp
+                    self.patch(again, top);
+                    for jump in [found].into_iter().chain(outside) {
+                        self.patch(jump, self.code.len());
+                    }
+                },
+                Op::MulAdd { targets, min, max } => {
+                    let outside = self.fits(*min, *max);
+                    self.emit(&[0x42, 0x0F, 0xB6, 0x04, 0x23]); // movzx eax, byte [rbx + r12]
+                    for (offset, factor) in targets {
+                        self.emit(&[0x69, 0xC8]); // imul ecx, eax, imm32
+                        self.imm32(*factor);
+                        self.emit(&[0x42, 0x00, 0x8C, 0x23]); // add byte [rbx + r12 + disp32], cl
+                        self.imm32(*offset);
+                    }
+                    self.emit(&[0x42, 0xC6, 0x04, 0x23, 0x00]); // mov byte [rbx + r12], 0
+                    for jump in outside {
+                        self.patch(jump, self.code.len());
+                    }
+                },
+            }
+        }
+        self.emit(&[0x31, 0xC0]); // xor eax, eax
+        let end = self.code.len();
+        for exit in std::mem::take(&mut self.exits) {
+            self.patch(exit, end);
+        }
+        self.epilogue();
+        self.code
+    }
+
+    fn prologue(&mut self) {
+        self.emit(&[0x53, 0x55, 0x41, 0x54, 0x41, 0x55, 0x41, 0x56, 0x41, 0x57]); // push rbx, rbp, r12-r15
+        self.emit(&[0x48, 0x83, 0xEC, 0x08]); // sub rsp, 8，保持栈 16 字节对齐
+        self.emit(&[0x49, 0x89, 0xFE]); // mov r14, rdi
+        self.emit(&[0x4D, 0x8B, 0x26]); // mov r12, [r14]
+        self.emit(&[0x4D, 0x8B, 0x6E, 0x08]); // mov r13, [r14 + 8]
+        self.emit(&[0x49, 0x8B, 0x5E, 0x10]); // mov rbx, [r14 + 16]
+        self.emit(&[0x4D, 0x8B, 0x7E, 0x18]); // mov r15, [r14 + 24]
+    }
+
+    fn epilogue(&mut self) {
+        self.emit(&[0x4D, 0x89, 0x26]); // mov [r14], r12
+        self.emit(&[0x4D, 0x89, 0x6E, 0x08]); // mov [r14 + 8], r13
+        self.emit(&[0x48, 0x83, 0xC4, 0x08]); // add rsp, 8
+        self.emit(&[0x41, 0x5F, 0x41, 0x5E, 0x41, 0x5D, 0x41, 0x5C, 0x5D, 0x5B]); // pop r15-r12, rbp, rbx
+        self.emit(&[0xC3]); // ret
+    }
+
+    /// 落在纸带内时直接移动，否则交给 `shift` 回调
+    fn shift(&mut self, offset: i64, index: usize) {
+        self.emit(&[0x49, 0x8D, 0x85]); // lea rax, [r13 + disp32]
+        self.imm32(offset);
+        self.emit(&[0x4C, 0x39, 0xF8]); // cmp rax, r15
+        let slow = self.jump(&[0x0F, 0x83]); // jae，负数也当作越界
+        self.emit(&[0x49, 0x89, 0xC5]); // mov r13, rax
+        self.emit(&[0x49, 0x89, 0xC4]); // mov r12, rax
+        let done = self.jump(&[0xE9]); // jmp
+        self.patch(slow, self.code.len());
+        self.emit(&[0x4C, 0x89, 0xF7]); // mov rdi, r14
+        self.emit(&[0x4C, 0x89, 0xEE]); // mov rsi, r13
+        self.emit(&[0x48, 0xBA]); // mov rdx, imm64
+        self.code.extend_from_slice(&offset.to_le_bytes());
+        self.call(shift as *const () as usize, index);
+        self.emit(&[0x4D, 0x8B, 0x26]); // mov r12, [r14]
+        self.emit(&[0x4D, 0x8B, 0x6E, 0x08]); // mov r13, [r14 + 8]
+        self.patch(done, self.code.len());
+    }
+
+    /// 调用回调函数，返回非 0 时以 `index + 1` 结束执行
+    fn call(&mut self, function: usize, index: usize) {
+        self.emit(&[0x48, 0xB8]); // mov rax, imm64
+        self.code.extend_from_slice(&(function as u64).to_le_bytes());
+        self.emit(&[0xFF, 0xD0]); // call rax
+        self.emit(&[0x48, 0x85, 0xC0]); // test rax, rax
+        let ok = self.jump(&[0x0F, 0x84]); // je
+        self.emit(&[0x48, 0xB8]); // mov rax, imm64
+        self.code.extend_from_slice(&(index as u64 + 1).to_le_bytes());
+        let exit = self.jump(&[0xE9]); // jmp
+        self.exits.push(exit);
+        self.patch(ok, self.code.len());
+    }
+
+    /// 检查指针未越界且 `r12 + min..=r12 + max` 都在纸带内，返回不满足时的跳转
+    fn fits(&mut self, min: i64, max: i64) -> [usize; 3] {
+        self.emit(&[0x4D, 0x39, 0xE5]); // cmp r13, r12
+        let overflowed = self.jump(&[0x0F, 0x85]); // jne
+        self.emit(&[0x49, 0x8D, 0x84, 0x24]); // lea rax, [r12 + disp32]
+        self.imm32(min);
+        self.emit(&[0x48, 0x85, 0xC0]); // test rax, rax
+        let left = self.jump(&[0x0F, 0x88]); // js
+        self.emit(&[0x49, 0x8D, 0x84, 0x24]); // lea rax, [r12 + disp32]
+        self.imm32(max);
+        self.emit(&[0x4C, 0x39, 0xF8]); // cmp rax, r15
+        let right = self.jump(&[0x0F, 0x8D]); // jge
+        [overflowed, left, right]
+    }
+
+    fn emit(&mut self, bytes: &[u8]) {
+        self.code.extend_from_slice(bytes);
+    }
+
+    fn imm32(&mut self, value: i64) {
+        self.code.extend_from_slice(&(value as i32).to_le_bytes());
+    }
+
+    /// 写出跳转指令，返回待回填的 rel32 位置
+    fn jump(&mut self, opcode: &[u8]) -> usize {
+        self.emit(opcode);
+        self.imm32(0);
+        self.code.len() - 4
+    }
+
+    fn patch(&mut self, at: usize, target: usize) {
+        let rel = target as i64 - (at as i64 + 4);
+        self.code[at..at + 4].copy_from_slice(&(rel as i32).to_le_bytes());
+    }
+}
+
+const PAGE_SIZE: usize = 4096;
+const PROT_READ: i32 = 1;
+const PROT_WRITE: i32 = 2;
+const PROT_EXEC: i32 = 4;
+const MAP_PRIVATE: i32 = 2;
+const MAP_ANONYMOUS: i32 = 0x20;
+const MAP_FAILED: *mut c_void = !0 as *mut c_void;
+
+extern "C" {
+    fn mmap(addr: *mut c_void, len: usize, prot: i32, flags: i32, fd: i32, offset: i64) -> *mut c_void;
+    fn mprotect(addr: *mut c_void, len: usize, prot: i32) -> i32;
+    fn munmap(addr: *mut c_void, len: usize) -> i32;
+}
@@ -1,126 +1,489 @@
-mod context;
-mod program;
+//! Brainfxxk 解释器，既可以在终端里可视化运行，也可以作为库嵌入使用。
+//!
+//! ```
+//! use std::collections::VecDeque;
+//! use brain_fxxker::{Options, Program, Step, Vm};
+//!
+//! let program = Program::from(",[.-]").unwrap();
+//! let input = VecDeque::from(vec![3]);
+//! let mut vm = Vm::new(program, Options::default(), input, Vec::new());
+//! assert!(matches!(vm.run_until(|_| false), Step::End));
+//! assert_eq!(vm.output(), &[3, 2, 1]);
+//! ```
+
+pub mod asm;
+pub mod c;
+pub mod error;
+pub mod ir;
+#[cfg(all(feature = "jit", target_arch = "x86_64", target_os = "linux"))]
+pub mod jit;
+pub mod profile;
+pub mod program;
+pub mod tape;
+#[doc(hidden)]
+pub mod testing;
+mod tui;
+pub mod vm;
+pub mod wat;
 
 use std::{
-    env::ArgsOs, ffi::OsString, ops::RangeBounds, process, str::FromStr, thread, time::Duration,
+    collections::VecDeque,
+    env::ArgsOs,
+    ffi::OsString,
+    fs,
+    io::{self, IsTerminal, Read, Write},
+    num::NonZeroUsize,
+    ops::RangeBounds,
+    str::FromStr,
 };
 
-use context::{Context, Step};
-use crossterm::event::{self, Event, KeyCode};
-
-pub fn run(config: Config) {
-    let dur = Duration::from_secs_f64(config.tick_duration);
-    let mut context = Context::new(config).unwrap_or_else(|err| {
-        eprintln!("{err}");
-        process::exit(1);
-    });
-
-    loop {
-        match context.step() {
-            Step::Next => thread::sleep(dur),
-            Step::End => break,
-            Step::Err(err) => {
-                eprintln!("{err}");
-                process::exit(1);
+pub use error::{ConfigError, Error, ParseError, RuntimeError, RuntimeErrorKind};
+pub use ir::Ir;
+pub use program::Program;
+pub use tape::Tape;
+pub use vm::{Input, Options, Output, Step, Vm};
+
+pub fn run(config: Config) -> Result<(), Error> {
+    let source = fs::read_to_string(&config.path)?;
+    let program = if config.debug_marker {
+        Program::with_debug_markers(&source)?
+    } else {
+        Program::from(&source)?
+    };
+
+    match config.command {
+        Command::Run if config.headless => {
+            let input: Box<dyn Input> = match config.input()? {
+                Some(bytes) => Box::new(VecDeque::from(bytes)),
+                None => Box::new(io::stdin()),
+            };
+            if config.profile {
+                run_profile(program, config.options(), input)
+            } else {
+                run_headless(program, config.options(), config.jit, input)
             }
-        }
+        },
+        Command::Run => {
+            let mut input = config.input()?;
+            // 键盘之外的标准输入只能是管道或文件，先全部读进来
+            if input.is_none() && !io::stdin().is_terminal() {
+                let mut bytes = Vec::new();
+                io::stdin().read_to_end(&mut bytes)?;
+                input = Some(bytes);
+            }
+            tui::run(program, &source, input, config)
+        },
+        Command::Compile => emit(c::compile(&Ir::new(program), config.options())?),
+        Command::Asm => emit(asm::compile(&Ir::new(program), config.options())?),
+        Command::Wat => emit(wat::compile(&Ir::new(program), config.options())?),
     }
+}
 
-    loop {
-        if let Ok(e) = event::read() {
-            if let Event::Key(key) = e {
-                if let KeyCode::Esc = key.code {
-                    break;
-                }
-            }
+fn emit(source: String) -> Result<(), Error> {
+    io::stdout().write_all(source.as_bytes())?;
+    Ok(())
+}
+
+fn run_headless(program: Program, options: Options, jit: bool, mut input: Box<dyn Input>) -> Result<(), Error> {
+    let result = execute(&Ir::new(program), options, jit, &mut input);
+    io::stdout().flush()?;
+    result?;
+    Ok(())
+}
+
+/// 逐条执行以统计每条指令的次数，结束或出错后在 stderr 打印报告
+fn run_profile(program: Program, options: Options, input: Box<dyn Input>) -> Result<(), Error> {
+    let mut vm = Vm::new(program, options, input, io::stdout());
+    let step = vm.run_until(|_| false);
+    io::stdout().flush()?;
+    eprint!("{}", profile::report(vm.program(), vm.counts(), vm.passes(), PROFILE_TOP));
+    match step {
+        Step::Err(err) => Err(Error::Runtime(err)),
+        _ => Ok(()),
+    }
+}
+
+/// 性能报告里列出几个最热的循环
+const PROFILE_TOP: usize = 10;
+
+/// 能用 JIT 时优先使用 JIT
+#[cfg(all(feature = "jit", target_arch = "x86_64", target_os = "linux"))]
+fn execute(ir: &Ir, options: Options, jit: bool, input: &mut Box<dyn Input>) -> Result<Tape, RuntimeError> {
+    if jit {
+        if let Some(jit) = jit::Jit::compile(ir, options) {
+            return jit.run(input, &mut io::stdout());
         }
     }
+    ir.run(options, input, &mut io::stdout())
+}
+
+#[cfg(not(all(feature = "jit", target_arch = "x86_64", target_os = "linux")))]
+fn execute(ir: &Ir, options: Options, _jit: bool, input: &mut Box<dyn Input>) -> Result<Tape, RuntimeError> {
+    ir.run(options, input, &mut io::stdout())
 }
 
+/// 指针越过纸带边界时的处理方式
+#[derive(Clone, Copy)]
 pub enum Overflow {
     Block,    // 指针在边界向外移动时，什么也不发生
     Overflow, // 指针能移动到边界外，但实际读写的是边界的内存
     Loop,     // 循环移动指针
     Exit,     // 指针在边界向外移动时，立即报错退出
+    Grow,     // 纸带按需向右（可选向左）扩展，超过上限时报错退出
+}
+
+/// 格子的值加减越过 0 或最大值时的处理方式
+#[derive(Clone, Copy)]
+pub enum CellOverflow {
+    Wrap,     // 回绕到另一端
+    Saturate, // 停在 0 或最大值
+    Exit,     // 立即报错退出
+}
+
+/// 每个格子的位数
+#[derive(Clone, Copy)]
+pub enum CellBits {
+    B8,
+    B16,
+    B32,
+    B64,
+}
+
+impl CellBits {
+    pub fn bits(self) -> u32 {
+        match self {
+            CellBits::B8 => 8,
+            CellBits::B16 => 16,
+            CellBits::B32 => 32,
+            CellBits::B64 => 64,
+        }
+    }
+
+    /// 格子能存的最大值
+    pub fn max(self) -> u64 {
+        u64::MAX >> (64 - self.bits())
+    }
+}
+
+/// `,` 遇到输入结束时怎样设置当前格子
+#[derive(Clone, Copy)]
+pub enum Eof {
+    Unchanged, // 保持不变
+    Zero,      // 设为 0
+    Max,       // 设为格子的最大值，即 8 位时的 255 或有符号看待时的 -1
+}
+
+/// 命令行的第一个参数，决定解释执行还是翻译成其他语言
+#[derive(Clone, Copy)]
+pub enum Command {
+    Run,
+    Compile, // 输出 C 源码
+    Asm,     // 输出 x86-64 Linux 的 GNU 汇编
+    Wat,     // 输出 WebAssembly 文本格式
 }
 
 pub struct Config {
+    command: Command,
     path: OsString,
     overflow: Overflow,
-    tape_length: usize,
+    cell_overflow: CellOverflow,
+    tape_length: Option<usize>, // 没有指定时按是否在终端里显示决定
+    tape_limit: usize,
+    grow_left: bool,
+    cell_bits: CellBits,
+    eof: Eof,
     window_width: usize,
     tick_duration: f64,
     output_as_int: bool,
+    headless: bool,
+    jit: bool,
+    input: Option<Source>,
+    summary: bool,
+    breakpoints: Vec<usize>, // 终端里执行到这些指令下标时暂停
+    debug_marker: bool,      // 源码里的 # 也当作断点
+    watches: Vec<Watch>,
+    profile: bool, // 统计每条指令和每个循环的执行次数，结束后打印报告
+    heatmap: bool, // 终端里按执行次数给代码上色
+    tape_display: TapeDisplay,
+    char_row: bool, // 终端里在格子下面再显示一行对应的字符
+    recent_steps: usize, // 终端里突出显示最近这么多步里写过的格子
+    source_lines: usize, // 终端里显示多少行原来的源码，为 0 时只显示一行指令
+    keys: Vec<String>,   // 命令行里给出过的参数名，按出现顺序
+}
+
+/// 终端里纸带格子的显示方式
+#[derive(Clone, Copy)]
+enum TapeDisplay {
+    Dec,    // 无符号十进制，补零
+    Hex,    // 十六进制，补零
+    Signed, // 按补码看作有符号的十进制
+    Bin,    // 二进制，补零
+    Char,   // 可打印的 ASCII 字符，其余显示为 ·
+}
+
+impl TapeDisplay {
+    /// 按下切换键后的下一种方式
+    fn next(self) -> TapeDisplay {
+        match self {
+            TapeDisplay::Dec => TapeDisplay::Hex,
+            TapeDisplay::Hex => TapeDisplay::Signed,
+            TapeDisplay::Signed => TapeDisplay::Bin,
+            TapeDisplay::Bin => TapeDisplay::Char,
+            TapeDisplay::Char => TapeDisplay::Dec,
+        }
+    }
+}
+
+/// 终端里监视的一段格子，值改变时暂停，给了 `value` 时只在变成这个值时暂停
+#[derive(Clone)]
+struct Watch {
+    start: i64, // 逻辑位置，包含两端
+    end: i64,
+    value: Option<u64>,
+}
+
+impl Watch {
+    fn contains(&self, position: i64) -> bool {
+        (self.start..=self.end).contains(&position)
+    }
+
+    /// 位置 `position` 的格子变成了 `value`，是否应该暂停
+    fn hit(&self, position: i64, value: u64) -> bool {
+        self.contains(position) && self.value.is_none_or(|target| target == value)
+    }
 }
 
-const KEY_VALUE_PAIRS: &str = "\
+/// `,` 的输入来源，没有指定时为标准输入或键盘
+enum Source {
+    File(OsString),
+    Text(Vec<u8>),
+}
+
+pub(crate) const KEY_VALUE_PAIRS: &str = "\
+Commands                  Output\n\
+<file_path>               run in the terminal\n\
+compile <file_path>       C source\n\
+asm <file_path>           x86-64 Linux GNU assembly\n\
+wat <file_path>           WebAssembly text format\n\
+\n\
 Keys                      Values\n\
-overflow                  Block | Overflow | Loop | Exit\n\
-tape_length               int in (0, 256]\n\
+overflow                  Block | Overflow | Loop | Exit | Grow\n\
+cell_overflow             Wrap | Saturate | Exit\n\
+tape_length               int in (0, 256] in the terminal, any positive int otherwise\n\
+tape_limit                positive int (Grow only)\n\
+grow_left                 true | false (Grow only, not for compile | asm | wat)\n\
+cell_bits                 8 | 16 | 32 | 64\n\
+eof                       Unchanged | Zero | Max (Ctrl-D ends input in the terminal)\n\
 window_width              even int in (0, 64]\n\
 tick_duration             float in [0, 3]\n\
-output_as_int             true | false";
+output_as_int             true | false\n\
+headless                  true | false\n\
+summary                   true | false (print executed commands after the terminal closes)\n\
+jit                       true | false (headless only)\n\
+input                     text fed to `,`, with \\n \\r \\t \\0 \\\\ \\xHH escapes\n\
+input_file                file fed to `,`\n\
+breakpoints               comma separated instruction indexes to pause at in the terminal\n\
+debug_marker              true | false (keep `#` in source as a breakpoint)\n\
+tape_display              Dec | Hex | Signed | Bin | Char\n\
+char_row                  true | false (show the character under each cell in the terminal)\n\
+source_lines              int in [0, 64], show this many lines of the original source in the terminal\n\
+recent_steps              int >= 0, highlight cells written within this many steps (the last step always)\n\
+heatmap                   true | false (colour code by run count, a step per tenth of the hottest)\n\
+profile                   true | false (report hottest loops to stderr, not for compile | asm | wat)\n\
+watch                     comma separated cells like 3 or 3-5 to pause at when changed, 3:65 to pause at 65,\n\
+                          negative ones like -3 or -5--2 with grow_left\n\
+\n\
+Without input or input_file, `,` reads piped stdin, otherwise the keyboard.\n\
+In the terminal: space pauses, n steps, b steps back, g goes back to a count,\n\
+h toggles the heatmap, m switches tape_display, +/- change speed, q or Esc quits.";
+
+/// 只在终端里有用的参数
+const TERMINAL_KEYS: [&str; 11] = [
+    "window_width",
+    "tick_duration",
+    "summary",
+    "breakpoints",
+    "debug_marker",
+    "heatmap",
+    "tape_display",
+    "char_row",
+    "recent_steps",
+    "source_lines",
+    "watch",
+];
+/// 只在运行程序时有用、翻译成其他语言时用不上的参数
+const RUN_KEYS: [&str; 4] = ["input", "input_file", "grow_left", "profile"];
+/// 只在不显示终端界面运行时有用的参数
+const HEADLESS_KEYS: [&str; 1] = ["jit"];
+
+/// 只在某些模式下有用的参数用在别处时的说明
+const ONLY_TERMINAL: &str = "only applies in the terminal UI, not with headless=true or compile | asm | wat";
+pub(crate) const ONLY_RUN: &str = "only applies when running the program, not to compile | asm | wat";
+const ONLY_HEADLESS: &str = "only applies with headless=true";
 
 impl Config {
-    pub fn new(mut args: ArgsOs) -> Result<Config, String> {
+    pub fn new(mut args: ArgsOs) -> Result<Config, ConfigError> {
         args.next();
-        let path = match args.next() {
+        let mut command = Command::Run;
+        let mut arg = args.next();
+        if let Some(name) = &arg {
+            if name == "compile" {
+                command = Command::Compile;
+            } else if name == "asm" {
+                command = Command::Asm;
+            } else if name == "wat" {
+                command = Command::Wat;
+            }
+            if !matches!(command, Command::Run) {
+                arg = args.next();
+            }
+        }
+        let path = match arg {
             Some(arg) => {
                 if arg == "help" {
-                    return Err(String::from(KEY_VALUE_PAIRS));
+                    return Err(ConfigError::Help);
                 } else {
                     arg
                 }
             }
-            None => {
-                return Err(String::from(
-                    "\
-            Didn't find brainfxxk source file!\n\
-            Usage: brain_fxxker.exe <file_path> <key>=<value> <...>\n\
-            For more information, use brain_fxxker.exe help.",
-                ))
-            }
+            None => return Err(ConfigError::MissingPath),
         };
 
-        let mut config = Config::default(path);
+        let mut config = Config::default(command, path);
         for arg in args {
-            let arg = match arg.clone().into_string() {
+            let arg = match arg.into_string() {
                 Ok(result) => result,
-                Err(_) => {
-                    return Err(format!(
-                        "Parameters shouldn't contain not unicode characters in \"{:?}\"!",
-                        arg
-                    ))
-                }
+                Err(arg) => return Err(ConfigError::NotUnicode(arg)),
             };
-            if let Err(discription) = config.parse(&arg) {
-                return Err(format!(
-                    "Parameter syntax error in \"{}\"! {}",
-                    arg, discription
-                ));
-            }
+            config.parse(&arg)?;
         }
+        config.check()?;
         Ok(config)
     }
 
-    fn default(path: OsString) -> Config {
+    fn default(command: Command, path: OsString) -> Config {
         Config {
+            command,
             path,
             overflow: Overflow::Block,
-            tape_length: 64,
+            cell_overflow: CellOverflow::Wrap,
+            tape_length: None,
+            tape_limit: 1 << 20,
+            grow_left: false,
+            cell_bits: CellBits::B8,
+            eof: Eof::Unchanged,
             window_width: 32,
             tick_duration: 0.02,
             output_as_int: false,
+            headless: false,
+            jit: true,
+            input: None,
+            summary: false,
+            breakpoints: Vec::new(),
+            debug_marker: false,
+            watches: Vec::new(),
+            profile: false,
+            heatmap: false,
+            tape_display: TapeDisplay::Dec,
+            char_row: false,
+            recent_steps: 16,
+            source_lines: 0,
+            keys: Vec::new(),
         }
     }
 
-    fn parse(&mut self, arg: &str) -> Result<(), &'static str> {
-        let lower_arg = arg.to_ascii_lowercase();
-        let key_value: Vec<_> = lower_arg.split('=').collect();
+    fn options(&self) -> Options {
+        Options {
+            overflow: self.overflow,
+            cell_overflow: self.cell_overflow,
+            tape_length: NonZeroUsize::new(self.tape_length()).expect("tape_length is positive"),
+            tape_limit: NonZeroUsize::new(self.tape_limit).expect("tape_limit is positive"),
+            grow_left: self.grow_left,
+            cell_bits: self.cell_bits,
+            eof: self.eof,
+            output_as_int: self.output_as_int,
+        }
+    }
+
+    /// 在终端里显示时纸带不能太长，其他情况默认给足 30000 个格子，`Grow` 则从一个格子开始扩展
+    fn tape_length(&self) -> usize {
+        let default = match (self.in_terminal(), self.overflow) {
+            (true, _) => 64,
+            (false, Overflow::Grow) => 1,
+            (false, _) => 30000,
+        };
+        self.tape_length.unwrap_or(default)
+    }
 
-        if key_value.len() != 2 {
-            return Err("Wrong parameter syntax, please use <key>=<value>.");
+    /// 读出 `input` 或 `input_file` 指定的输入，都没有指定时返回 `None`
+    fn input(&self) -> io::Result<Option<Vec<u8>>> {
+        match &self.input {
+            Some(Source::File(path)) => fs::read(path).map(Some),
+            Some(Source::Text(bytes)) => Ok(Some(bytes.clone())),
+            None => Ok(None),
         }
+    }
+
+    fn in_terminal(&self) -> bool {
+        matches!(self.command, Command::Run) && !self.headless
+    }
+
+    /// 检查需要几个参数一起决定的限制
+    fn check(&self) -> Result<(), ConfigError> {
+        let invalid = |key: &str, value: String| ConfigError::InvalidValue { key: String::from(key), value };
+        let unsupported = |key: &str, reason: &str| ConfigError::Unsupported {
+            key: String::from(key),
+            reason: String::from(reason),
+        };
+        if self.in_terminal() && self.tape_length() > 256 {
+            return Err(invalid("tape_length", self.tape_length().to_string()));
+        }
+        if let Overflow::Grow = self.overflow {
+            if self.tape_limit < self.tape_length() {
+                return Err(invalid("tape_limit", self.tape_limit.to_string()));
+            }
+        }
+        let run = matches!(self.command, Command::Run);
+        for key in &self.keys {
+            let key = key.as_str();
+            if TERMINAL_KEYS.contains(&key) && !self.in_terminal() {
+                return Err(unsupported(key, ONLY_TERMINAL));
+            }
+            if RUN_KEYS.contains(&key) && !run {
+                return Err(unsupported(key, ONLY_RUN));
+            }
+            if HEADLESS_KEYS.contains(&key) && !(run && self.headless) {
+                return Err(unsupported(key, ONLY_HEADLESS));
+            }
+        }
+        let max = self.cell_bits.max();
+        for watch in &self.watches {
+            let value = match watch.value {
+                Some(value) => format!("{}-{}:{}", watch.start, watch.end, value),
+                None => format!("{}-{}", watch.start, watch.end),
+            };
+            // 只有向左扩展时才有负下标的格子
+            let negative = watch.start < 0 && !(matches!(self.overflow, Overflow::Grow) && self.grow_left);
+            if negative || watch.value.unwrap_or(0) > max {
+                return Err(invalid("watch", value));
+            }
+        }
+        Ok(())
+    }
+
+    fn parse(&mut self, arg: &str) -> Result<(), ConfigError> {
+        let lower_arg = arg.to_ascii_lowercase();
+        // 值里可能还有 '='，只在第一个 '=' 处分开
+        let key_value = match lower_arg.split_once('=') {
+            Some((key, value)) => [key, value],
+            None => return Err(ConfigError::Syntax(String::from(arg))),
+        };
+        // input 和 input_file 的值区分大小写
+        let raw_value = &arg[key_value[0].len() + 1..];
+        let invalid = || ConfigError::InvalidValue {
+            key: String::from(key_value[0]),
+            value: String::from(key_value[1]),
+        };
 
         match key_value[0] {
             "overflow" => self.overflow = match key_value[1] {
@@ -128,35 +491,182 @@ impl Config {
                 "overflow" => Overflow::Overflow,
                 "exit" => Overflow::Exit,
                 "loop" => Overflow::Loop,
-                _ => return Err("Wrong overflow value."),
+                "grow" => Overflow::Grow,
+                _ => return Err(invalid()),
+            },
+            "cell_overflow" => self.cell_overflow = match key_value[1] {
+                "wrap" => CellOverflow::Wrap,
+                "saturate" => CellOverflow::Saturate,
+                "exit" => CellOverflow::Exit,
+                _ => return Err(invalid()),
+            },
+            "tape_length" => match arg_to(key_value[1], 1..) {
+                Ok(value) => self.tape_length = Some(value),
+                Err(_) => return Err(invalid()),
             },
-            "tape_length" => match arg_to(key_value[1], 1..=256) {
-                Ok(value) => self.tape_length = value,
-                Err(_) => return Err("Wrong tape_length value."),
+            "tape_limit" => match arg_to(key_value[1], 1..) {
+                Ok(value) => self.tape_limit = value,
+                Err(_) => return Err(invalid()),
+            },
+            "grow_left" => self.grow_left = match key_value[1] {
+                "true" => true,
+                "false" => false,
+                _ => return Err(invalid()),
+            },
+            "cell_bits" => self.cell_bits = match key_value[1] {
+                "8" => CellBits::B8,
+                "16" => CellBits::B16,
+                "32" => CellBits::B32,
+                "64" => CellBits::B64,
+                _ => return Err(invalid()),
+            },
+            "eof" => self.eof = match key_value[1] {
+                "unchanged" => Eof::Unchanged,
+                "zero" => Eof::Zero,
+                "max" => Eof::Max,
+                _ => return Err(invalid()),
             },
             "window_width" => match arg_to(key_value[1], 1..=64) {
-                Ok(value) => if value % 2 == 0 {
-                    self.window_width = value;
-                } else {
-                    return Err("Window_width must be even");
-                },
-                Err(_) => return Err("Wrong window_width value."),
+                Ok(value) if value % 2 == 0 => self.window_width = value,
+                _ => return Err(invalid()),
             },
             "tick_duration" => match arg_to(key_value[1], 0.0..=3.0) {
                 Ok(value) => self.tick_duration = value,
-                Err(_) => return Err("Wrong tick_duration value."),
+                Err(_) => return Err(invalid()),
             },
             "output_as_int" => self.output_as_int = match key_value[1] {
                 "true" => true,
                 "false" => false,
-                _ => return Err("Wrong output_as_int value."),
+                _ => return Err(invalid()),
+            },
+            "headless" => self.headless = match key_value[1] {
+                "true" => true,
+                "false" => false,
+                _ => return Err(invalid()),
+            },
+            "jit" => self.jit = match key_value[1] {
+                "true" => true,
+                "false" => false,
+                _ => return Err(invalid()),
+            },
+            "summary" => self.summary = match key_value[1] {
+                "true" => true,
+                "false" => false,
+                _ => return Err(invalid()),
+            },
+            "input" => match unescape(raw_value) {
+                Some(bytes) => self.input = Some(Source::Text(bytes)),
+                None => return Err(invalid()),
+            },
+            "breakpoints" => {
+                for index in key_value[1].split(',') {
+                    match arg_to(index.trim(), 0..) {
+                        Ok(value) => self.breakpoints.push(value),
+                        Err(_) => return Err(invalid()),
+                    }
+                }
+            },
+            "profile" => self.profile = match key_value[1] {
+                "true" => true,
+                "false" => false,
+                _ => return Err(invalid()),
             },
-            _ => return Err("Given key doesn't exist."),
+            "tape_display" => self.tape_display = match key_value[1] {
+                "dec" => TapeDisplay::Dec,
+                "hex" => TapeDisplay::Hex,
+                "signed" => TapeDisplay::Signed,
+                "bin" => TapeDisplay::Bin,
+                "char" => TapeDisplay::Char,
+                _ => return Err(invalid()),
+            },
+            "char_row" => self.char_row = match key_value[1] {
+                "true" => true,
+                "false" => false,
+                _ => return Err(invalid()),
+            },
+            "source_lines" => match arg_to(key_value[1], 0..=64) {
+                Ok(value) => self.source_lines = value,
+                Err(_) => return Err(invalid()),
+            },
+            "recent_steps" => match arg_to(key_value[1], 0..) {
+                Ok(value) => self.recent_steps = value,
+                Err(_) => return Err(invalid()),
+            },
+            "heatmap" => self.heatmap = match key_value[1] {
+                "true" => true,
+                "false" => false,
+                _ => return Err(invalid()),
+            },
+            "watch" => {
+                for item in key_value[1].split(',') {
+                    match parse_watch(item.trim()) {
+                        Some(watch) => self.watches.push(watch),
+                        None => return Err(invalid()),
+                    }
+                }
+            },
+            "debug_marker" => self.debug_marker = match key_value[1] {
+                "true" => true,
+                "false" => false,
+                _ => return Err(invalid()),
+            },
+            "input_file" => self.input = Some(Source::File(OsString::from(raw_value))),
+            key => return Err(ConfigError::UnknownKey(String::from(key))),
         }
+        self.keys.push(String::from(key_value[0]));
         Ok(())
     }
 }
 
+/// 解析 `input=` 里的 `\n`、`\r`、`\t`、`\0`、`\\` 和 `\xHH` 转义，其余字符按 UTF-8 编码
+fn unescape(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            bytes.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
+            continue;
+        }
+        let byte = match chars.next()? {
+            'n' => b'\n',
+            'r' => b'\r',
+            't' => b'\t',
+            '0' => 0,
+            '\\' => b'\\',
+            'x' => {
+                let hex: String = chars.by_ref().take(2).collect();
+                if hex.len() != 2 || !hex.chars().all(|ch| ch.is_ascii_hexdigit()) {
+                    return None;
+                }
+                u8::from_str_radix(&hex, 16).ok()?
+            },
+            _ => return None,
+        };
+        bytes.push(byte);
+    }
+    Some(bytes)
+}
+
+/// 解析 `watch=` 里的一项：`下标` 或 `起点-终点`，后面可以跟 `:值`，向左扩展出的格子下标为负
+fn parse_watch(item: &str) -> Option<Watch> {
+    let (range, value) = match item.split_once(':') {
+        Some((range, value)) => (range, Some(arg_to(value, 0..).ok()?)),
+        None => (item, None),
+    };
+    // 开头的 `-` 是负号，之后的第一个 `-` 才分隔起点和终点
+    let (start, end) = match range.char_indices().skip(1).find(|&(_, c)| c == '-') {
+        Some((index, _)) => (arg_to(&range[..index], ..).ok()?, arg_to(&range[index + 1..], ..).ok()?),
+        None => {
+            let index = arg_to(range, ..).ok()?;
+            (index, index)
+        },
+    };
+    if start > end {
+        return None;
+    }
+    Some(Watch { start, end, value })
+}
+
 fn arg_to<T, R>(arg: &str, range: R) -> Result<T, ()>
 where
     T: FromStr + PartialOrd<T>,
@@ -172,3 +682,103 @@ where
         Err(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(command: Command, args: &[&str]) -> Result<Config, ConfigError> {
+        let mut config = Config::default(command, OsString::from("program.bf"));
+        for arg in args {
+            config.parse(arg)?;
+        }
+        config.check()?;
+        Ok(config)
+    }
+
+    /// 参数在这些命令下被当作用不了的参数拒绝
+    fn assert_unsupported(arg: &str, extra: &[&str], command: Command, reason: &str) {
+        let args: Vec<&str> = extra.iter().copied().chain([arg]).collect();
+        match config(command, &args) {
+            Err(ConfigError::Unsupported { key, reason: actual }) => {
+                assert_eq!(arg.split_once('=').unwrap().0, key);
+                assert_eq!(actual, reason, "{arg}");
+            },
+            Err(err) => panic!("{arg}: {err}"),
+            Ok(_) => panic!("{arg} should be unsupported with {extra:?}"),
+        }
+    }
+
+    const TRANSLATIONS: [Command; 3] = [Command::Compile, Command::Asm, Command::Wat];
+
+    #[test]
+    fn terminal_keys() {
+        let args = [
+            "window_width=16",
+            "tick_duration=0",
+            "summary=true",
+            "breakpoints=1,2",
+            "debug_marker=true",
+            "heatmap=true",
+            "tape_display=hex",
+            "char_row=true",
+            "recent_steps=4",
+            "source_lines=3",
+            "watch=3,5-7:65",
+        ];
+        for arg in args {
+            assert!(config(Command::Run, &[arg]).is_ok(), "{arg}");
+            assert_unsupported(arg, &["headless=true"], Command::Run, ONLY_TERMINAL);
+            for command in TRANSLATIONS {
+                assert_unsupported(arg, &[], command, ONLY_TERMINAL);
+            }
+        }
+    }
+
+    #[test]
+    fn run_keys() {
+        for arg in ["input=abc", "input_file=input.txt", "grow_left=true", "profile=true"] {
+            assert!(config(Command::Run, &[arg]).is_ok(), "{arg}");
+            assert!(config(Command::Run, &["headless=true", arg]).is_ok(), "{arg}");
+            for command in TRANSLATIONS {
+                assert_unsupported(arg, &[], command, ONLY_RUN);
+            }
+        }
+    }
+
+    #[test]
+    fn headless_keys() {
+        assert!(config(Command::Run, &["headless=true", "jit=false"]).is_ok());
+        assert_unsupported("jit=false", &[], Command::Run, ONLY_HEADLESS);
+        for command in TRANSLATIONS {
+            assert_unsupported("jit=true", &[], command, ONLY_HEADLESS);
+        }
+    }
+
+    #[test]
+    fn watch_negative_cells() {
+        let grow_left = ["overflow=grow", "grow_left=true"];
+        let parsed = config(Command::Run, &[&grow_left[..], &["watch=-3,-5--2:65,-1-2"]].concat()).unwrap();
+        let ranges: Vec<_> = parsed.watches.iter().map(|watch| (watch.start, watch.end, watch.value)).collect();
+        assert_eq!(ranges, [(-3, -3, None), (-5, -2, Some(65)), (-1, 2, None)]);
+
+        // 不向左扩展时没有负下标的格子
+        for arg in ["watch=-3", "watch=-5--2"] {
+            assert!(matches!(config(Command::Run, &[arg]), Err(ConfigError::InvalidValue { .. })), "{arg}");
+        }
+        for arg in ["watch=3--2", "watch=-", "watch=--3", "watch=-2--5"] {
+            assert!(config(Command::Run, &[&grow_left[..], &[arg]].concat()).is_err(), "{arg}");
+        }
+    }
+
+    #[test]
+    fn keys_for_every_mode() {
+        for arg in ["overflow=exit", "cell_bits=16", "eof=zero", "output_as_int=true", "tape_length=8"] {
+            assert!(config(Command::Run, &[arg]).is_ok(), "{arg}");
+            assert!(config(Command::Run, &["headless=true", arg]).is_ok(), "{arg}");
+            for command in TRANSLATIONS {
+                assert!(config(command, &[arg]).is_ok(), "{arg}");
+            }
+        }
+    }
+}
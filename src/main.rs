@@ -1,12 +1,24 @@
 use std::env;
 use std::process;
 
-use brain_fxxker::{self, Config};
+use brain_fxxker::{self, Config, ConfigError, Error};
 
 fn main() {
     let config = Config::new(env::args_os()).unwrap_or_else(|err| {
+        if let ConfigError::Help = err {
+            println!("{err}");
+            process::exit(0);
+        }
         eprintln!("Problem parsing arguments: {err}");
-        process::exit(1);
+        process::exit(2);
     });
-    brain_fxxker::run(config);
+    if let Err(err) = brain_fxxker::run(config) {
+        eprintln!("{err}");
+        process::exit(match err {
+            Error::Config(_) => 2,
+            Error::Parse(_) => 3,
+            Error::Runtime(_) => 4,
+            Error::Io(_) => 5,
+        });
+    }
 }
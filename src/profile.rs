@@ -0,0 +1,111 @@
+use std::fmt::Write;
+
+use crate::program::{Key, Program};
+
+/// 一个循环的执行统计
+pub struct Loop {
+    pub start: usize,    // [ 的下标
+    pub end: usize,      // ] 的下标
+    pub iterations: u64, // 循环体执行的轮数
+    pub commands: u64,   // 循环里（包括嵌套的循环）执行的指令数
+}
+
+/// 由每条指令执行的次数 `counts` 和每个 `[` 进入循环体的次数 `passes` 统计所有循环，
+/// 按执行的指令数从多到少排列，两者都由 `Vm` 给出
+pub fn loops(program: &Program, counts: &[u64], passes: &[u64]) -> Vec<Loop> {
+    let mut sums = vec![0];
+    for count in counts {
+        sums.push(sums[sums.len() - 1] + count);
+    }
+    let mut loops = Vec::new();
+    for start in 0..program.len() {
+        if let Some(Key::If(end)) = program.get(start) {
+            loops.push(Loop {
+                start,
+                end: *end,
+                iterations: passes[start],
+                commands: sums[end + 1] - sums[start],
+            });
+        }
+    }
+    loops.sort_by(|a, b| b.commands.cmp(&a.commands).then(a.start.cmp(&b.start)));
+    loops
+}
+
+/// 性能报告：一共执行的指令数，以及最热的 `top` 个循环的位置、占比和轮数
+pub fn report(program: &Program, counts: &[u64], passes: &[u64], top: usize) -> String {
+    let total: u64 = counts.iter().sum();
+    let mut out = String::new();
+    let _ = writeln!(out, "Profile: {} commands executed", total);
+    let loops: Vec<Loop> = loops(program, counts, passes)
+        .into_iter()
+        .filter(|hot| hot.commands > 0)
+        .take(top)
+        .collect();
+    if loops.is_empty() {
+        let _ = writeln!(out, "No loop was executed.");
+        return out;
+    }
+    let _ = writeln!(out, "Hottest loops:");
+    let _ = writeln!(out, "  share  location               iterations  loop");
+    for hot in loops {
+        let location = program.location(hot.start).unwrap_or_default();
+        let place = format!("line {}, column {}", location.line, location.column);
+        let share = hot.commands as f64 / total as f64 * 100.0;
+        let _ = writeln!(
+            out,
+            "{:>6.2}%  {:<22} {:>10}  {}",
+            share,
+            place,
+            hot.iterations,
+            text(program, hot.start, hot.end)
+        );
+    }
+    out
+}
+
+/// 循环的源码，太长时截断
+fn text(program: &Program, start: usize, end: usize) -> String {
+    const WIDTH: usize = 32;
+    let mut out: String = (start..=end.min(start + WIDTH))
+        .filter_map(|index| program.get(index).map(Key::symbol))
+        .collect();
+    if end - start >= WIDTH {
+        out.truncate(WIDTH - 3);
+        out.push_str("...");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::*;
+    use crate::{Options, Step, Vm};
+
+    /// 执行完后按 `[` 的下标列出每个循环的轮数和执行的指令数
+    fn profile(source: &str) -> Vec<(usize, u64, u64)> {
+        let program = Program::from(source).unwrap();
+        let mut vm = Vm::new(program, Options::default(), VecDeque::new(), Vec::new());
+        assert!(matches!(vm.run_until(|_| false), Step::End));
+        let mut loops: Vec<_> = loops(vm.program(), vm.counts(), vm.passes())
+            .into_iter()
+            .map(|hot| (hot.start, hot.iterations, hot.commands))
+            .collect();
+        loops.sort();
+        loops
+    }
+
+    #[test]
+    fn nested_loop_at_the_start_of_the_body() {
+        // 外层循环只走一轮，里面的两个 [-] 各走三轮
+        assert_eq!(profile("+++[[-]+>+++[-]<-]"), [(3, 1, 27), (4, 3, 9), (12, 3, 9)]);
+    }
+
+    #[test]
+    fn skipped_and_repeated_loops() {
+        // 第一个循环遇到 0 直接跳过，第二个循环的每一轮都把里面的循环跑两轮
+        assert_eq!(profile("[-]++[>++[-]<-]"), [(0, 0, 2), (5, 2, 26), (9, 4, 12)]);
+    }
+}
@@ -1,3 +1,6 @@
+use crate::error::{ParseError, Unmatched};
+
+/// 一条 brainfxxk 指令，括号里记录了与之配对的括号的下标
 pub enum Key {
     Right,
     Left, // > <
@@ -7,18 +10,77 @@ pub enum Key {
     In, // . ,
     If(usize),
     Back(usize), // [ ]
+    Debug,       // #，只在保留断点标记时出现，执行时什么也不做
+}
+
+impl Key {
+    /// 指令在源码中的字符
+    pub fn symbol(&self) -> char {
+        match self {
+            Key::Right => '>',
+            Key::Left => '<',
+            Key::Add => '+',
+            Key::Sub => '-',
+            Key::Out => '.',
+            Key::In => ',',
+            Key::If(_) => '[',
+            Key::Back(_) => ']',
+            Key::Debug => '#',
+        }
+    }
 }
 
+/// 指令在源码中的位置，行号和列号从 1 开始，列号按字符计
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Location {
+    pub offset: usize, // 字节偏移
+    pub line: usize,
+    pub column: usize,
+}
+
+/// 字符在终端里占几格，只粗略地把中日韩文字、全角符号和表情算作两格
+pub(crate) fn char_width(ch: char) -> usize {
+    match ch as u32 {
+        0x1100..=0x115F
+        | 0x2E80..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1FAFF
+        | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+/// 去掉注释并完成括号配对的程序
 pub struct Program {
     code: Vec<Key>,
+    locations: Vec<Location>, // 每条指令在源码中的位置
 }
 
 impl Program {
-    pub fn from(source: String) -> Result<Program, String> {
-        let syntax_error = Err(String::from("Brainfxxk source syntax error!"));
+    /// 解析源码，非指令字符都当作注释忽略
+    pub fn from(source: &str) -> Result<Program, ParseError> {
+        Program::parse(source, false)
+    }
 
+    /// 同 `from`，但把 `#` 保留为断点标记 `Key::Debug`
+    pub fn with_debug_markers(source: &str) -> Result<Program, ParseError> {
+        Program::parse(source, true)
+    }
+
+    fn parse(source: &str, debug: bool) -> Result<Program, ParseError> {
         let mut code = Vec::new();
-        for ch in source.chars() {
+        let mut locations = Vec::new();
+        let (mut line, mut column) = (1, 0);
+        for (offset, ch) in source.char_indices() {
+            if ch == '\n' {
+                (line, column) = (line + 1, 0);
+                continue;
+            }
+            column += 1;
             code.push(match ch {
                 '>' => Key::Right,
                 '<' => Key::Left,
@@ -28,19 +90,20 @@ impl Program {
                 ',' => Key::In,
                 '[' => Key::If(0),
                 ']' => match code.last() {
-                    Some(key) => match key {
-                        Key::If(_) => {
-                            code.pop();
-                            continue;
-                        }
-                        _ => Key::Back(0),
-                    },
-                    None => return syntax_error,
+                    Some(Key::If(_)) => {
+                        code.pop();
+                        locations.pop();
+                        continue;
+                    }
+                    _ => Key::Back(0),
                 },
+                '#' if debug => Key::Debug,
                 _ => continue,
             });
+            locations.push(Location { offset, line, column });
         }
 
+        let mut unmatched = Vec::new();
         let mut stack = Vec::new();
         for (index, key) in code.iter_mut().enumerate() {
             match key {
@@ -52,40 +115,43 @@ impl Program {
                             *if_index = index;
                         }
                     }
-                    None => return syntax_error,
+                    None => unmatched.push(Unmatched::new(']', locations[index], source)),
                 },
                 _ => (),
             }
         }
-        if stack.len() != 0 {
-            syntax_error
-        } else {
-            Ok(Program { code })
+        for (index, _) in stack {
+            unmatched.push(Unmatched::new('[', locations[index], source));
+        }
+        if !unmatched.is_empty() {
+            unmatched.sort_by_key(|bracket| bracket.location.offset);
+            return Err(ParseError { unmatched });
         }
+        Ok(Program { code, locations })
     }
 
     pub fn get(&self, index: usize) -> Option<&Key> {
         self.code.get(index)
     }
 
+    /// 第 `index` 条指令在源码中的位置
+    pub fn location(&self, index: usize) -> Option<Location> {
+        self.locations.get(index).copied()
+    }
+
     pub fn len(&self) -> usize {
         self.code.len()
     }
 
-    pub fn slice_string(&self, left: usize, width: usize) -> String {
+    pub fn is_empty(&self) -> bool {
+        self.code.is_empty()
+    }
+
+    pub(crate) fn slice_string(&self, left: usize, width: usize) -> String {
         let right = self.code.len().min(left + width);
         let mut out = String::new();
         for key in &self.code[left..right] {
-            out.push(match key {
-                Key::Right => '>',
-                Key::Left => '<',
-                Key::Add => '+',
-                Key::Sub => '-',
-                Key::Out => '.',
-                Key::In => ',',
-                Key::If(_) => '[',
-                Key::Back(_) => ']',
-            });
+            out.push(key.symbol());
             out.push(' ');
         }
 
@@ -0,0 +1,234 @@
+use crate::{vm::Options, CellOverflow, Overflow};
+
+/// 纸带和指针，指针越界时按 `Overflow` 处理，格子的值越界时按 `CellOverflow` 处理
+///
+/// 每个格子统一存成 `u64`，按 `cell_bits` 截断。
+/// `Overflow::Grow` 下纸带按需扩展，向左扩展后 `cells()[0]` 的逻辑位置为负数。
+pub struct Tape {
+    cells: Vec<u64>,
+    max: u64,
+    cell_overflow: CellOverflow,
+    pointer: usize,
+    virtual_pointer: i64,
+    overflow: Overflow,
+    origin: i64, // cells[0] 的逻辑位置
+    low: i64,    // 到过的最左的逻辑位置，向左扩展时会多分配一些格子
+    limit: usize,
+    grow_left: bool,
+}
+
+impl Tape {
+    pub(crate) fn new(options: Options) -> Tape {
+        Tape {
+            cells: vec![0; options.tape_length.get()],
+            max: options.cell_bits.max(),
+            cell_overflow: options.cell_overflow,
+            pointer: 0,
+            virtual_pointer: 0,
+            overflow: options.overflow,
+            origin: 0,
+            low: 0,
+            limit: options.tape_limit.get(),
+            grow_left: options.grow_left,
+        }
+    }
+
+    /// 当前格子的值
+    pub fn get(&self) -> u64 {
+        self.cells[self.pointer]
+    }
+
+    /// 写入当前格子，超出格子宽度的部分被截掉
+    pub(crate) fn set(&mut self, value: u64) {
+        self.cells[self.pointer] = value & self.max;
+    }
+
+    pub fn cells(&self) -> &[u64] {
+        &self.cells
+    }
+
+    /// 格子能存的最大值
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+
+    /// 调用者负责按 `max` 截断写入的值
+    pub(crate) fn current_mut(&mut self) -> &mut u64 {
+        &mut self.cells[self.pointer]
+    }
+
+    /// 调用者负责按 `max` 截断写入的值
+    pub(crate) fn cells_mut(&mut self) -> &mut [u64] {
+        &mut self.cells
+    }
+
+    /// 实际读写的格子下标
+    pub fn pointer(&self) -> usize {
+        self.pointer
+    }
+
+    /// 指针的逻辑位置，`Overflow::Overflow` 下可能在纸带之外
+    pub fn virtual_pointer(&self) -> i64 {
+        self.virtual_pointer
+    }
+
+    /// `cells()[0]` 的逻辑位置，只有向左扩展过才不是 0
+    pub fn origin(&self) -> i64 {
+        self.origin
+    }
+
+    /// 按 `CellOverflow` 规则给当前格子加上 `delta`，`Exit` 越界时返回 `Err` 且格子不变
+    pub(crate) fn add(&mut self, delta: i64) -> Result<(), ()> {
+        let value = self.get() as i128 + delta as i128;
+        let max = self.max as i128;
+        let value = match self.cell_overflow {
+            CellOverflow::Wrap => value.rem_euclid(max + 1),
+            CellOverflow::Saturate => value.clamp(0, max),
+            CellOverflow::Exit if (0..=max).contains(&value) => value,
+            CellOverflow::Exit => return Err(()),
+        };
+        self.set(value as u64);
+        Ok(())
+    }
+
+    /// 加减是否一定回绕，此时乘法循环等可以一步算完
+    pub(crate) fn wraps(&self) -> bool {
+        matches!(self.cell_overflow, CellOverflow::Wrap)
+    }
+
+    /// 按 `Overflow` 规则移动指针，`Exit` 越界或 `Grow` 无法扩展时返回 `Err` 且指针不动
+    pub(crate) fn shift(&mut self, offset: i64) -> Result<(), ()> {
+        let target = self.virtual_pointer + offset;
+        if let Overflow::Grow = self.overflow {
+            self.grow(target)?;
+            self.virtual_pointer = target;
+            self.pointer = (target - self.origin) as usize;
+            return Ok(());
+        }
+        let (virtual_pointer, pointer) = locate(target, self.cells.len(), self.overflow).ok_or(())?;
+        self.virtual_pointer = virtual_pointer;
+        self.pointer = pointer;
+        Ok(())
+    }
+
+    /// 扩展纸带直到包含逻辑位置 `target`，分配的格子不超过 `limit` 个
+    ///
+    /// 到过的范围超过 `limit` 个格子，或不允许向左扩展时返回 `Err`。
+    fn grow(&mut self, target: i64) -> Result<(), ()> {
+        let len = self.cells.len() as i64;
+        let high = self.origin + len - 1;
+        let low = self.low.min(target);
+        if (low < 0 && !self.grow_left) || high.max(target) - low >= self.limit as i64 {
+            return Err(());
+        }
+        if target > high {
+            // 向左多分配的格子还没到过，都是 0，超出上限时先去掉
+            let origin = self.origin.max(target - self.limit as i64 + 1);
+            if origin > self.origin {
+                self.cells.drain(..(origin - self.origin) as usize);
+                self.pointer -= (origin - self.origin) as usize;
+                self.origin = origin;
+            }
+            // Vec 自己会成倍扩容，这里只加需要的格子
+            self.cells.resize((target - self.origin + 1) as usize, 0);
+        } else if target < self.origin {
+            // 左端按 2 的幂对齐，结果与一次移动几格无关
+            let origin = -(target.unsigned_abs().next_power_of_two() as i64);
+            let origin = origin.max(high - self.limit as i64 + 1);
+            self.cells.splice(0..0, (origin..self.origin).map(|_| 0));
+            self.origin = origin;
+        }
+        self.low = low;
+        Ok(())
+    }
+
+    /// 指针处在纸带内，且相对下标 `pointer` 的 `min..=max` 都在到过的范围内，
+    /// 此时可以不经 `shift` 直接读写这些格子
+    pub(crate) fn fits(&self, pointer: usize, min: i64, max: i64) -> bool {
+        self.virtual_pointer - self.origin == self.pointer as i64
+            && pointer as i64 + min >= self.low - self.origin
+            && pointer as i64 + max < self.cells.len() as i64
+    }
+
+    /// 直接设定指针，调用者保证位置符合 `Overflow` 规则
+    pub(crate) fn seek(&mut self, pointer: usize, virtual_pointer: i64) {
+        self.pointer = pointer;
+        self.virtual_pointer = virtual_pointer;
+    }
+}
+
+/// 按 `Overflow` 修正指针的逻辑位置，返回修正后的逻辑位置和实际读写的下标
+///
+/// `Exit` 下越界时返回 `None`，`Grow` 由 `Tape::shift` 扩展纸带，不经过这里。
+pub(crate) fn locate(virtual_pointer: i64, len: usize, overflow: Overflow) -> Option<(i64, usize)> {
+    let last = len as i64 - 1;
+    if (0..=last).contains(&virtual_pointer) {
+        return Some((virtual_pointer, virtual_pointer as usize));
+    }
+    let edge = if virtual_pointer < 0 { 0 } else { last };
+    match overflow {
+        Overflow::Block => Some((edge, edge as usize)),
+        Overflow::Overflow => Some((virtual_pointer, edge as usize)),
+        Overflow::Loop => {
+            let virtual_pointer = virtual_pointer.rem_euclid(len as i64);
+            Some((virtual_pointer, virtual_pointer as usize))
+        },
+        Overflow::Exit | Overflow::Grow => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use super::*;
+
+    fn grow(tape_limit: usize, grow_left: bool) -> Tape {
+        Tape::new(Options {
+            overflow: Overflow::Grow,
+            tape_length: NonZeroUsize::MIN,
+            tape_limit: NonZeroUsize::new(tape_limit).unwrap(),
+            grow_left,
+            ..Options::default()
+        })
+    }
+
+    #[test]
+    fn grow_right() {
+        let mut tape = grow(4, false);
+        for _ in 0..3 {
+            tape.shift(1).unwrap();
+        }
+        assert_eq!(tape.cells().len(), 4);
+        assert!(tape.shift(1).is_err());
+        assert!(tape.shift(-4).is_err());
+        assert_eq!(tape.virtual_pointer(), 3);
+    }
+
+    #[test]
+    fn grow_left_pads_to_a_power_of_two() {
+        let mut tape = grow(16, true);
+        tape.shift(-3).unwrap();
+        assert_eq!(tape.origin(), -4);
+        assert_eq!(tape.cells().len(), 5);
+        assert_eq!(tape.pointer(), 1);
+    }
+
+    #[test]
+    fn padding_counts_toward_the_limit() {
+        let mut tape = grow(10, true);
+        for offset in [-1, -1, -1, 1, 1, 1, 1, 1, 1, 1, 1, 1] {
+            tape.shift(offset).unwrap();
+            assert!(tape.cells().len() <= 10);
+        }
+        assert_eq!(tape.cells().len(), 10);
+        assert_eq!(tape.origin(), -3);
+        assert_eq!(tape.virtual_pointer(), 6);
+        assert_eq!(tape.pointer() as i64 + tape.origin(), 6);
+        // 到过的范围已经有 10 个格子
+        assert!(tape.shift(1).is_err());
+        assert!(tape.shift(-10).is_err());
+        tape.shift(-9).unwrap();
+        assert_eq!(tape.pointer(), 0);
+    }
+}
@@ -0,0 +1,28 @@
+//! 测试用的公共数据，供本库和 tests/ 下的各个后端测试共用，不属于公开接口
+
+use std::collections::VecDeque;
+
+use crate::{Options, Program, Step, Vm};
+
+/// 会被 `Ir` 换成快速路径的循环
+const LOOPS: [&str; 9] = ["[-]", "[>]", "[<]", "[<<]", "[>>>]", "[->+>++<<]", "[-<+>]", "[<->-]", "[->>>+<<<]"];
+
+/// 让指针停在纸带两端附近，格子有大有小，`Scan` 和 `MulAdd` 碰到边界时退回逐条执行
+const PREFIXES: [&str; 15] = [
+    "", "+", "-", "+>+>+>+", "+>+>+", "+>+>+>+<", ">>>-", "-<", "+<+<+", "+++>++>+<<", "++>+++++<", ">>>--<<<--",
+    "+>+>++>+<<<", ">>>>>+", "<<<<+",
+];
+
+/// 长度为 4 的纸带两端附近执行各种快速路径的程序，最后输出几个格子
+pub fn edge_programs() -> impl Iterator<Item = String> {
+    PREFIXES
+        .into_iter()
+        .flat_map(|prefix| LOOPS.into_iter().map(move |body| format!("{prefix}{body}.>.<<.")))
+}
+
+/// 先用 `Vm` 试跑，十万步之内结束或出错时返回 `true`，停不下来的程序在各个后端都会死循环，不必比较
+pub fn halts(source: &str, options: Options) -> bool {
+    let program = Program::from(source).unwrap();
+    let mut vm = Vm::new(program, options, VecDeque::new(), Vec::new());
+    !matches!(vm.run_until(|vm| vm.command_executed() == 100_000), Step::Next)
+}
@@ -0,0 +1,731 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+use crossterm::{
+    queue,
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    terminal::{self, ClearType},
+    cursor,
+    style::{self, Color, Stylize}
+};
+
+use crate::{
+    error::{RuntimeError, RuntimeErrorKind},
+    profile,
+    program::{char_width, Key, Program},
+    vm::{Input, Step, Vm},
+    Config, Error, TapeDisplay, Watch, PROFILE_TOP,
+};
+
+/// `,` 的输入，事先给定时从中读取，否则读键盘，并记下已读入的字节
+pub struct Feed {
+    given: Option<VecDeque<u8>>,
+    consumed: Vec<Option<u8>>,
+}
+
+impl Feed {
+    pub fn new(given: Option<Vec<u8>>) -> Feed {
+        Feed {
+            given: given.map(VecDeque::from),
+            consumed: Vec::new(),
+        }
+    }
+
+    /// 显示用的已读入内容，去掉倒退时退回的最后 `unread` 个
+    ///
+    /// 控制字符转义后再显示，免得打乱界面，键盘上的 Ctrl-D 显示为 `^D`。
+    fn show(&self, unread: usize) -> String {
+        let mut out = String::new();
+        for byte in &self.consumed[..self.consumed.len() - unread] {
+            match byte {
+                Some(byte) if byte.is_ascii_graphic() || *byte == b' ' => out.push(*byte as char),
+                Some(byte) => out.extend(std::ascii::escape_default(*byte).map(char::from)),
+                None if self.given.is_none() => out.push_str("^D"),
+                None => (),
+            }
+        }
+        out
+    }
+}
+
+impl Input for Feed {
+    fn read(&mut self) -> io::Result<Option<u8>> {
+        if let Some(given) = &mut self.given {
+            let byte = given.pop_front();
+            self.consumed.push(byte);
+            return Ok(byte);
+        }
+        loop {
+            // Windows 上按一次键盘会有按下和松开两个 event，只看按下
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                // 等待输入时 Esc 和 Ctrl-C 用来退出
+                if key.code == KeyCode::Esc
+                    || key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL)
+                {
+                    return Err(io::Error::from(io::ErrorKind::Interrupted));
+                }
+                if key.code == KeyCode::Char('d') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                    // Ctrl-D 表示输入结束
+                    self.consumed.push(None);
+                    return Ok(None);
+                }
+                if let KeyCode::Char(ch) = key.code {
+                    self.consumed.push(Some(ch as u8));
+                    return Ok(Some(ch as u8));
+                }
+            }
+        }
+    }
+}
+
+pub fn run(program: Program, source: &str, input: Option<Vec<u8>>, config: Config) -> Result<(), Error> {
+    let mut vm = Vm::new(program, config.options(), Feed::new(input), Vec::new());
+    vm.record_history(HISTORY);
+    let result = show(&mut vm, source, &config);
+
+    // 离开备用屏幕之后，把输出留在真正的终端里
+    let mut stdout = io::stdout();
+    stdout.write_all(vm.output())?;
+    let report = config.summary || config.profile;
+    if report && !vm.output().is_empty() && !vm.output().ends_with(b"\n") {
+        writeln!(stdout)?;
+    }
+    if config.summary {
+        writeln!(stdout, "Executed commands: {}", vm.command_executed())?;
+    }
+    stdout.flush()?;
+    if config.profile {
+        eprint!("{}", profile::report(vm.program(), vm.counts(), vm.passes(), PROFILE_TOP));
+    }
+    result
+}
+
+/// 最多能倒退的步数
+const HISTORY: usize = 1 << 20;
+
+/// 在备用屏幕里逐步执行并绘制，返回时 `Tui` 已经关闭
+///
+/// 运行时空格暂停或继续，`n` 单步，`b` 倒退一步，`g` 输入执行次数跳回当时，
+/// `+`/`-` 加速或减速，`q` 或 Esc 立即退出。
+/// 执行到断点（指定的指令下标或源码里的 `#`）之前自动暂停。
+fn show(vm: &mut Vm<Feed, Vec<u8>>, source: &str, config: &Config) -> Result<(), Error> {
+    let mut tick = Duration::from_secs_f64(config.tick_duration);
+    let mut ended = false;
+    let mut next = Instant::now();
+    let mut tui = Tui::new(config, source, vm.program())?;
+    let breakpoint = |vm: &Vm<Feed, Vec<u8>>| {
+        let program_counter = vm.program_counter();
+        config.breakpoints.contains(&program_counter)
+            || matches!(vm.program().get(program_counter), Some(Key::Debug))
+    };
+
+    tui.refresh(vm)?;
+    let mut paused = breakpoint(vm);
+    let mut watched = false; // 因监视的格子变化而暂停
+    loop {
+        let state = if ended {
+            String::from("Finished")
+        } else if paused && watched {
+            String::from("Watchpoint")
+        } else if paused && breakpoint(vm) {
+            String::from("Breakpoint")
+        } else if paused {
+            String::from("Paused")
+        } else {
+            format!("Tick {:.3}s", tick.as_secs_f64())
+        };
+        tui.status(&format!("{state}──space:pause n:step b:back g:goto +/-:speed q:quit"))?;
+
+        // 暂停或结束时一直等按键，运行时最多等到下一步
+        let running = !paused && !ended;
+        let timeout = running.then(|| next.saturating_duration_since(Instant::now()));
+        let mut step = false;
+        let control = control(timeout)?;
+        if control.is_some() {
+            watched = false;
+        }
+        match control {
+            Some(Control::Quit) => return Ok(()),
+            Some(Control::Pause) => paused = !paused,
+            Some(Control::Step) => {
+                paused = true;
+                step = true;
+            },
+            Some(Control::Back) => {
+                paused = true;
+                if vm.step_back() {
+                    ended = false;
+                    tui.refresh(vm)?;
+                }
+            },
+            Some(Control::Goto) => {
+                paused = true;
+                if let Some(target) = goto(&mut tui, vm)? {
+                    while vm.command_executed() > target && vm.step_back() {
+                        ended = false;
+                    }
+                }
+                tui.refresh(vm)?;
+            },
+            Some(Control::Display) => {
+                tui.tape_display = tui.tape_display.next();
+                tui.refresh(vm)?;
+            },
+            Some(Control::Heatmap) => {
+                tui.heatmap = !tui.heatmap;
+                tui.refresh(vm)?;
+            },
+            Some(Control::Faster) => tick = faster(tick),
+            Some(Control::Slower) => tick = slower(tick),
+            None => step = running && Instant::now() >= next,
+        }
+        if !step || ended {
+            continue;
+        }
+
+        // 只有当前格子可能被改写
+        let cell = vm.tape().pointer() as i64 + vm.tape().origin();
+        let before = vm.tape().get();
+        match vm.step() {
+            Step::Next => {
+                tui.refresh(vm)?;
+                let after = vm.tape().cells()[(cell - vm.tape().origin()) as usize];
+                watched = after != before && config.watches.iter().any(|watch| watch.hit(cell, after));
+                paused = paused || watched || breakpoint(vm);
+            },
+            Step::End => ended = true,
+            // 等待输入时按 Esc 也算退出
+            Step::Err(RuntimeError { kind: RuntimeErrorKind::Input(err), .. })
+                if err.kind() == io::ErrorKind::Interrupted =>
+            {
+                return Ok(())
+            },
+            Step::Err(err) => return Err(Error::Runtime(err)),
+        }
+        next = Instant::now() + tick;
+    }
+}
+
+/// 间隔减半，不到 1ms 就不再等待
+fn faster(tick: Duration) -> Duration {
+    if tick > MIN_TICK {
+        tick / 2
+    } else {
+        Duration::ZERO
+    }
+}
+
+/// 间隔加倍，不超过配置允许的 3s
+fn slower(tick: Duration) -> Duration {
+    (tick * 2).clamp(MIN_TICK, Duration::from_secs(3))
+}
+
+const MIN_TICK: Duration = Duration::from_millis(1);
+
+/// 播放控制的按键
+enum Control {
+    Pause,
+    Step,
+    Back,
+    Goto,
+    Heatmap,
+    Display,
+    Faster,
+    Slower,
+    Quit,
+}
+
+/// 等待一次按键，`timeout` 为 `None` 时一直等，超时或不是控制键时返回 `None`
+fn control(timeout: Option<Duration>) -> io::Result<Option<Control>> {
+    if let Some(timeout) = timeout {
+        if !event::poll(timeout)? {
+            return Ok(None);
+        }
+    }
+    let key = match event::read()? {
+        Event::Key(key) if key.kind == KeyEventKind::Press => key,
+        _ => return Ok(None),
+    };
+    Ok(match key.code {
+        KeyCode::Char(' ') => Some(Control::Pause),
+        KeyCode::Char('n') => Some(Control::Step),
+        KeyCode::Char('b') => Some(Control::Back),
+        KeyCode::Char('g') => Some(Control::Goto),
+        KeyCode::Char('h') => Some(Control::Heatmap),
+        KeyCode::Char('m') => Some(Control::Display),
+        KeyCode::Char('+') => Some(Control::Faster),
+        KeyCode::Char('-') => Some(Control::Slower),
+        KeyCode::Char('q') | KeyCode::Esc => Some(Control::Quit),
+        // raw mode 下 Ctrl-C 不再发出信号，当作退出
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Some(Control::Quit),
+        _ => None,
+    })
+}
+
+/// 在状态栏里输入要跳回的执行次数，Enter 确认，Esc 取消
+fn goto(tui: &mut Tui, vm: &Vm<Feed, Vec<u8>>) -> io::Result<Option<u64>> {
+    let earliest = vm.command_executed() - vm.history_len() as u64;
+    let mut digits = String::new();
+    loop {
+        tui.status(&format!("Go to {digits}_──from {earliest} Enter:go Esc:cancel"))?;
+        let key = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            _ => continue,
+        };
+        match key.code {
+            KeyCode::Char(ch) if ch.is_ascii_digit() && digits.len() < 19 => digits.push(ch),
+            KeyCode::Backspace => {
+                digits.pop();
+            },
+            KeyCode::Enter => return Ok(digits.parse().ok()),
+            KeyCode::Esc => return Ok(None),
+            _ => (),
+        }
+    }
+}
+
+/// 终端界面，只观察 `Vm` 的状态并绘制出来
+pub struct Tui {
+    window_width: usize,
+    program_left: usize,
+    tape_left: i64,
+    watches: Vec<Watch>,
+    heatmap: bool,
+    tape_display: TapeDisplay,
+    char_row: bool, // 开启时纸带框多一行，下面的内容都下移一行
+    recent_steps: usize,
+    source_rows: u16,    // 代码框显示几行源码，为 0 时只显示一行指令和指针
+    source: Vec<String>, // 显示多行源码时才有，每行一项
+    source_top: usize,   // 最上面显示的是第几行，从 0 开始
+    source_left: usize,  // 每行从第几个字符开始显示
+    instructions: HashMap<(usize, usize), usize>, // 源码中的 (行, 列) 到指令下标
+    _screen: Screen,
+}
+
+impl Tui {
+    pub fn new(config: &Config, source: &str, program: &Program) -> Result<Tui, io::Error> {
+        let _screen = Screen::new(config.window_width as u16, config.source_lines as u16, config.char_row)?;
+        let mut instructions = HashMap::new();
+        if config.source_lines > 0 {
+            for index in 0..program.len() {
+                if let Some(location) = program.location(index) {
+                    instructions.insert((location.line, location.column), index);
+                }
+            }
+        }
+        Ok(Tui {
+            window_width: config.window_width,
+            program_left: 0,
+            tape_left: 0,
+            watches: config.watches.clone(),
+            heatmap: config.heatmap,
+            tape_display: config.tape_display,
+            char_row: config.char_row,
+            recent_steps: config.recent_steps,
+            source_rows: config.source_lines as u16,
+            source: match config.source_lines {
+                0 => Vec::new(),
+                _ => source.lines().map(String::from).collect(),
+            },
+            source_top: 0,
+            source_left: 0,
+            instructions,
+            _screen,
+        })
+    }
+
+    pub fn refresh(&mut self, vm: &Vm<Feed, Vec<u8>>) -> io::Result<()> {
+        let width = self.window_width;
+        let len = 2 * width as u16;
+        let program_counter = vm.program_counter();
+        queue!(
+            io::stdout(),
+            cursor::MoveTo(7, 0),
+            style::Print(label(program_counter as i64, len.saturating_sub(7))),
+        )?;
+        if self.source_rows > 0 {
+            self.source_view(vm)?;
+        } else {
+            self.code_row(vm)?;
+        }
+        let legend = if self.heatmap { self.legend(vm)? } else { 0 };
+        queue!(
+            io::stdout(),
+            cursor::MoveTo(20, self.row(3)),
+            style::Print(label(vm.command_executed() as i64, len.saturating_sub(20 + legend))),
+        )?;
+
+        // 每个格子按显示方式等宽显示，能放几个就放几个
+        let max = vm.tape().max();
+        let digits = cell_width(self.tape_display, max);
+        let width = (len as usize / (digits + 1)).max(1) as i64;
+        let virtual_pointer = vm.tape().virtual_pointer();
+
+        if virtual_pointer < self.tape_left {
+            self.tape_left = virtual_pointer;
+        } else if virtual_pointer >= self.tape_left + width {
+            self.tape_left = virtual_pointer - width + 1;
+        }
+        let origin = vm.tape().origin();
+        let left = self.tape_left - origin;
+        let tape_output = slice_tape(vm.tape().cells(), left, width, self.tape_display, max);
+        let tape_pin = (digits + 1) * (virtual_pointer - self.tape_left) as usize + digits / 2 + 1;
+        let tape_pin = tape_pin.min(len as usize - 1) as u16;
+        queue!(
+            io::stdout(),
+            cursor::MoveTo(7, self.row(4)),
+            style::Print(label(virtual_pointer, len.saturating_sub(7))),
+            cursor::MoveTo(0, self.row(6)),
+            terminal::Clear(ClearType::CurrentLine),
+            style::Print('│'),
+            cursor::MoveTo(tape_pin, self.row(6)),
+            style::Print('^'),
+            cursor::MoveTo(len, self.row(6)),
+            style::Print('│'),
+            cursor::MoveTo(1, self.row(5)),
+        )?;
+        // 监视的格子加下划线，上一步写过的反色，最近几步写过的标黄，太宽时截掉放不下的部分
+        let recent = vm.recent_writes(self.recent_steps.max(1));
+        let mut room = len as usize - 1;
+        for (column, (index, text)) in tape_output.into_iter().enumerate() {
+            if column > 0 {
+                if room == 0 {
+                    break;
+                }
+                queue!(io::stdout(), style::Print(' '))?;
+                room -= 1;
+            }
+            let text: String = text.chars().take(room).collect();
+            room -= text.chars().count();
+            let position = index + origin;
+            let mut styled = text.stylize();
+            if self.watches.iter().any(|watch| watch.contains(position)) {
+                styled = styled.underlined();
+            }
+            match recent.iter().filter(|write| write.0 == position).map(|write| write.1).min() {
+                Some(0) => styled = styled.reverse(),
+                Some(_) if self.recent_steps > 0 => styled = styled.yellow(),
+                _ => (),
+            }
+            queue!(io::stdout(), style::PrintStyledContent(styled))?;
+        }
+        // 换了显示方式后可能变短
+        queue!(io::stdout(), style::Print(" ".repeat(room)))?;
+        if self.char_row {
+            let cells = vm.tape().cells();
+            let chars: Vec<String> = (left..left + width)
+                .map(|index| {
+                    let value = usize::try_from(index).ok().and_then(|index| cells.get(index));
+                    let ch = value.and_then(|&value| printable(value)).unwrap_or(' ');
+                    format!("{ch:^digits$}")
+                })
+                .collect();
+            let chars: String = chars.join(" ").chars().take(len as usize - 1).collect();
+            queue!(
+                io::stdout(),
+                cursor::MoveTo(1, self.row(5) + 1),
+                style::Print(format!("{chars:<0$}", len as usize - 1)),
+            )?;
+        }
+
+        // 倒退时输入输出会变短，先清掉旧的
+        let output: String = vm.output().iter().map(|&byte| byte as char).collect();
+        queue!(
+            io::stdout(),
+            cursor::MoveTo(0, self.row(9)),
+            terminal::Clear(ClearType::CurrentLine),
+            style::Print(vm.input().show(vm.unread_len())),
+            cursor::MoveTo(0, self.row(12)),
+            terminal::Clear(ClearType::FromCursorDown),
+        )?;
+        // raw mode 下换行不会回到行首，逐行定位
+        for (row, line) in output.split('\n').enumerate() {
+            queue!(io::stdout(), cursor::MoveTo(0, self.row(12) + row as u16), style::Print(line))?;
+        }
+
+        io::stdout().flush()
+    }
+
+    /// 一行只有指令的代码，下面一行用 `^` 指出当前指令，开了热度图时按执行次数上色
+    fn code_row(&mut self, vm: &Vm<Feed, Vec<u8>>) -> io::Result<()> {
+        let width = self.window_width;
+        let len = 2 * width as u16;
+        let program_counter = vm.program_counter();
+        if program_counter < self.program_left {
+            self.program_left = program_counter;
+        } else if program_counter >= self.program_left + width {
+            self.program_left = program_counter - width + 1;
+        }
+
+        queue!(io::stdout(), cursor::MoveTo(1, 1))?;
+        if self.heatmap {
+            let counts = vm.counts();
+            let max = counts.iter().copied().max().unwrap_or(0);
+            for index in self.program_left..self.program_left + width {
+                if index > self.program_left {
+                    queue!(io::stdout(), style::Print(' '))?;
+                }
+                match (vm.program().get(index), counts.get(index)) {
+                    (Some(key), Some(&count)) => queue!(
+                        io::stdout(),
+                        style::PrintStyledContent(key.symbol().with(heat(count, max)))
+                    )?,
+                    _ => queue!(io::stdout(), style::Print(' '))?,
+                }
+            }
+        } else {
+            queue!(io::stdout(), style::Print(vm.program().slice_string(self.program_left, width)))?;
+        }
+
+        let program_pin = 2 * (program_counter - self.program_left) as u16 + 1;
+        queue!(
+            io::stdout(),
+            cursor::MoveTo(0, 2),
+            terminal::Clear(ClearType::CurrentLine),
+            style::Print('│'),
+            cursor::MoveTo(program_pin, 2),
+            style::Print('^'),
+            cursor::MoveTo(len, 2),
+            style::Print('│'),
+        )
+    }
+
+    /// 多行显示原来的源码，当前指令反色，开了热度图时指令按执行次数上色
+    ///
+    /// 当前指令所在的行和列总在可见范围内，行太长时左右滚动。
+    fn source_view(&mut self, vm: &Vm<Feed, Vec<u8>>) -> io::Result<()> {
+        let room = 2 * self.window_width - 1;
+        let rows = self.source_rows as usize;
+        let program_counter = vm.program_counter();
+        if let Some(location) = vm.program().location(program_counter) {
+            let (line, column) = (location.line - 1, location.column - 1);
+            if line < self.source_top {
+                self.source_top = line;
+            } else if line >= self.source_top + rows {
+                self.source_top = line - rows + 1;
+            }
+            let text = &self.source[line];
+            if column < self.source_left {
+                self.source_left = column;
+            }
+            while text_width(text, self.source_left, column + 1) > room {
+                self.source_left += 1;
+            }
+        }
+
+        let counts = vm.counts();
+        let max = counts.iter().copied().max().unwrap_or(0);
+        for row in 0..rows {
+            let line = self.source_top + row;
+            let text = self.source.get(line).map_or("", String::as_str);
+            let mut room = room;
+            queue!(io::stdout(), cursor::MoveTo(1, 1 + row as u16))?;
+            for (column, ch) in text.chars().enumerate().skip(self.source_left) {
+                // 制表符等控制字符按一格空白显示，与列号一致
+                let ch = if ch.is_control() { ' ' } else { ch };
+                if char_width(ch) > room {
+                    break;
+                }
+                room -= char_width(ch);
+                let mut styled = ch.stylize();
+                if let Some(&index) = self.instructions.get(&(line + 1, column + 1)) {
+                    if self.heatmap {
+                        styled = styled.with(heat(counts[index], max));
+                    }
+                    if index == program_counter {
+                        styled = styled.reverse();
+                    }
+                }
+                queue!(io::stdout(), style::PrintStyledContent(styled))?;
+            }
+            queue!(io::stdout(), style::Print(" ".repeat(room)))?;
+        }
+        Ok(())
+    }
+
+    /// 在代码框的下边框右端画出热度图的图例，返回图例的宽度
+    fn legend(&self, vm: &Vm<Feed, Vec<u8>>) -> io::Result<u16> {
+        // 从冷到热：没执行过、每差十倍一级，最后是最多的次数
+        let max = vm.counts().iter().copied().max().unwrap_or(0).to_string();
+        let width = (HEAT.len() + max.len() + 6) as u16;
+        let len = 2 * self.window_width as u16;
+        // 放不下时不画，让位给执行次数
+        if width + 20 + vm.command_executed().to_string().len() as u16 > len {
+            return Ok(0);
+        }
+        queue!(io::stdout(), cursor::MoveTo(len - width, self.row(3)), style::Print(" 0 "))?;
+        for color in [Color::DarkGrey].iter().chain(HEAT.iter().rev()) {
+            queue!(io::stdout(), style::PrintStyledContent('■'.with(*color)))?;
+        }
+        queue!(io::stdout(), style::Print(format!(" {max} ")))?;
+        Ok(width)
+    }
+
+    /// 在纸带框的下边框上显示运行状态
+    pub fn status(&mut self, text: &str) -> io::Result<()> {
+        let width = 2 * self.window_width - 1;
+        let text: String = format!("{text:─<width$}").chars().take(width).collect();
+        queue!(io::stdout(), cursor::MoveTo(1, self.row(7)), style::Print(text))?;
+        io::stdout().flush()
+    }
+
+    /// 布局按代码框里只有一行指令、纸带框里没有字符行编排，换算出第 `row` 行实际所在的行
+    fn row(&self, row: u16) -> u16 {
+        let mut actual = row;
+        if row >= 3 && self.source_rows > 0 {
+            actual = actual + self.source_rows - 2;
+        }
+        if row >= 6 && self.char_row {
+            actual += 1;
+        }
+        actual
+    }
+}
+
+/// 热度的颜色，从最热开始，每差十倍降一级
+const HEAT: [Color; 5] = [Color::Red, Color::Yellow, Color::Green, Color::Cyan, Color::Blue];
+
+/// 按执行次数与最热指令相差的数量级选颜色，没执行过的为灰色
+fn heat(count: u64, max: u64) -> Color {
+    if count == 0 {
+        return Color::DarkGrey;
+    }
+    let mut scale = 10;
+    for color in HEAT {
+        if count as u128 * scale > max as u128 {
+            return color;
+        }
+        scale *= 10;
+    }
+    Color::Blue
+}
+
+/// 第 `start` 到第 `end` 个字符（不含）在终端里占几格
+fn text_width(text: &str, start: usize, end: usize) -> usize {
+    text.chars().skip(start).take(end.saturating_sub(start)).map(char_width).sum()
+}
+
+/// 边框上的数字，后面补上边框线盖住上次留下的更长的数字
+fn label(value: i64, width: u16) -> String {
+    format!("{:─<width$}", value, width = width as usize)
+}
+
+/// 从下标 `left` 起的 `width` 个格子，返回每格的下标和按 `display` 等宽显示的值，纸带外的显示为 `-`
+fn slice_tape(tape: &[u64], left: i64, width: i64, display: TapeDisplay, max: u64) -> Vec<(i64, String)> {
+    let tape_range = 0..tape.len() as i64;
+    let digits = cell_width(display, max);
+    (left..left + width)
+        .map(|index| {
+            let text = if tape_range.contains(&index) {
+                show_cell(tape[index as usize], display, max)
+            } else {
+                "-".repeat(digits)
+            };
+            (index, text)
+        })
+        .collect()
+}
+
+/// 格子能存的最大值为 `max` 时，按 `display` 显示一个格子需要几个字符
+fn cell_width(display: TapeDisplay, max: u64) -> usize {
+    let bits = 64 - max.leading_zeros() as usize;
+    match display {
+        TapeDisplay::Dec => max.to_string().len(),
+        TapeDisplay::Hex => bits / 4,
+        TapeDisplay::Signed => (max / 2 + 1).to_string().len() + 1,
+        TapeDisplay::Bin => bits,
+        TapeDisplay::Char => 1,
+    }
+}
+
+fn show_cell(value: u64, display: TapeDisplay, max: u64) -> String {
+    let width = cell_width(display, max);
+    match display {
+        TapeDisplay::Dec => format!("{:0width$}", value),
+        TapeDisplay::Hex => format!("{:0width$x}", value),
+        TapeDisplay::Signed => {
+            // 最高位为 1 的按补码当作负数
+            let signed = if value > max / 2 {
+                value as i128 - max as i128 - 1
+            } else {
+                value as i128
+            };
+            format!("{:>width$}", signed)
+        },
+        TapeDisplay::Bin => format!("{:0width$b}", value),
+        TapeDisplay::Char => String::from(printable(value).unwrap_or('·')),
+    }
+}
+
+/// 可打印的 ASCII 字符
+fn printable(value: u64) -> Option<char> {
+    (0x20..0x7f).contains(&value).then_some(value as u8 as char)
+}
+
+struct Screen;
+
+impl Screen {
+    fn new(window_width: u16, source_rows: u16, char_row: bool) -> Result<Screen, io::Error> {
+        // 按键要逐个读到，不等回车
+        terminal::enable_raw_mode()?;
+        let length = window_width * 2 - 1;
+        let mut line = String::new();
+        for _ in 0..length {
+            line.push('─');
+        }
+        queue!(
+            io::stdout(),
+            terminal::EnterAlternateScreen,
+            cursor::Hide,
+            terminal::DisableLineWrap,
+            cursor::MoveTo(0, 0),
+            style::Print("┌"), style::Print(&line), style::Print("┐"), cursor::MoveToColumn(1), style::PrintStyledContent("Code@ ".bold()), cursor::MoveToNextLine(1),
+        )?;
+        // 显示多行源码时代码框有 source_rows 行，否则是一行指令加一行指针
+        for _ in 0..if source_rows > 0 { source_rows } else { 2 } {
+            queue!(io::stdout(), style::Print("│"), cursor::MoveRight(length), style::Print("│"), cursor::MoveToNextLine(1))?;
+        }
+        queue!(
+            io::stdout(),
+            style::Print("└"), style::Print(&line), style::Print("┘"), cursor::MoveToColumn(1), style::PrintStyledContent("Executed commands: ".bold()), cursor::MoveToNextLine(1),
+            style::Print("┌"), style::Print(&line), style::Print("┐"), cursor::MoveToColumn(1), style::PrintStyledContent("Tape@ ".bold()), cursor::MoveToNextLine(1),
+            style::Print("│"), cursor::MoveRight(length), style::Print("│"), cursor::MoveToNextLine(1),
+        )?;
+        if char_row {
+            queue!(io::stdout(), style::Print("│"), cursor::MoveRight(length), style::Print("│"), cursor::MoveToNextLine(1))?;
+        }
+        queue!(
+            io::stdout(),
+            style::Print("│"), cursor::MoveRight(length), style::Print("│"), cursor::MoveToNextLine(1),
+            style::Print("└"), style::Print(&line), style::Print("┘"), cursor::MoveToNextLine(1),
+            style::PrintStyledContent("Input:".bold()), cursor::MoveToNextLine(3),
+            //
+            //
+            style::PrintStyledContent("Output:".bold()), cursor::MoveToNextLine(3),
+            //
+            //
+            style::Print(" "),
+        )?;
+        io::stdout().flush()?;
+        Ok(Screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = queue!(
+            io::stdout(),
+            terminal::LeaveAlternateScreen,
+            cursor::Show,
+            terminal::EnableLineWrap,
+        );
+        let _ = io::stdout().flush();
+        let _ = terminal::disable_raw_mode();
+    }
+}
\ No newline at end of file
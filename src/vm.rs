@@ -0,0 +1,630 @@
+use std::{
+    collections::VecDeque,
+    io::{self, Read, Write},
+    num::NonZeroUsize,
+};
+
+use crate::{
+    error::{RuntimeError, RuntimeErrorKind},
+    program::{Key, Program},
+    tape::Tape,
+    CellBits, CellOverflow, ConfigError, Eof, Overflow, ONLY_RUN,
+};
+
+/// `,` 的输入来源，返回 `None` 表示输入已经结束
+pub trait Input {
+    fn read(&mut self) -> io::Result<Option<u8>>;
+}
+
+/// `.` 的输出去向
+pub trait Output {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<()>;
+}
+
+impl Input for io::Stdin {
+    fn read(&mut self) -> io::Result<Option<u8>> {
+        // 交互使用时先把提示输出刷出去
+        io::stdout().flush()?;
+        let mut buf = [0];
+        match Read::read(&mut self.lock(), &mut buf)? {
+            0 => Ok(None),
+            _ => Ok(Some(buf[0])),
+        }
+    }
+}
+
+impl Input for VecDeque<u8> {
+    fn read(&mut self) -> io::Result<Option<u8>> {
+        Ok(self.pop_front())
+    }
+}
+
+impl<I: Input + ?Sized> Input for Box<I> {
+    fn read(&mut self) -> io::Result<Option<u8>> {
+        (**self).read()
+    }
+}
+
+impl Output for io::Stdout {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.write_all(bytes)
+    }
+}
+
+impl Output for Vec<u8> {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+/// 虚拟机的运行参数
+#[derive(Clone, Copy)]
+pub struct Options {
+    pub overflow: Overflow,
+    pub cell_overflow: CellOverflow,
+    /// 纸带的长度，`Overflow::Grow` 下为初始长度
+    pub tape_length: NonZeroUsize,
+    /// `Overflow::Grow` 下最多用到多少个格子
+    pub tape_limit: NonZeroUsize,
+    /// `Overflow::Grow` 下是否允许向左扩展
+    pub grow_left: bool,
+    pub cell_bits: CellBits,
+    pub eof: Eof,
+    /// `.` 输出十进制数字加空格，而不是原始字节
+    pub output_as_int: bool,
+}
+
+impl Options {
+    /// 翻译成其他语言时纸带长度固定，不向左扩展的 `Grow` 等价于长度为 `tape_limit` 的 `Exit`，
+    /// 向左扩展的无法这样换算，返回错误
+    pub(crate) fn fixed(self) -> Result<Options, ConfigError> {
+        match self.overflow {
+            Overflow::Grow if self.grow_left => Err(ConfigError::Unsupported {
+                key: String::from("grow_left"),
+                reason: String::from(ONLY_RUN),
+            }),
+            Overflow::Grow => Ok(Options {
+                overflow: Overflow::Exit,
+                tape_length: self.tape_limit.max(self.tape_length),
+                ..self
+            }),
+            _ => Ok(self),
+        }
+    }
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            overflow: Overflow::Block,
+            cell_overflow: CellOverflow::Wrap,
+            tape_length: NonZeroUsize::new(64).unwrap(),
+            tape_limit: NonZeroUsize::new(1 << 20).unwrap(),
+            grow_left: false,
+            cell_bits: CellBits::B8,
+            eof: Eof::Unchanged,
+            output_as_int: false,
+        }
+    }
+}
+
+/// 逐条执行 `Program` 的虚拟机，`,` 从 `I` 读取，`.` 写入 `O`
+pub struct Vm<I, O> {
+    program: Program,
+    program_counter: usize,
+    command_executed: u64,
+    counts: Vec<u64>, // 每条指令执行过的次数
+    passes: Vec<u64>, // 每个 `[` 进入循环体的次数
+    tape: Tape,
+    eof: Eof,
+    output_as_int: bool,
+    input: I,
+    output: O,
+    history: VecDeque<Undo>,
+    history_limit: usize,    // 为 0 时不记录
+    unread: Vec<Option<u8>>, // 撤销 `,` 时退回的输入，再次执行 `,` 时先读这里
+}
+
+/// 撤销一步所需的记录，保存的都是执行前的状态
+struct Undo {
+    program_counter: usize,
+    change: Change,
+}
+
+enum Change {
+    Jump,                                     // 只改变了 program_counter
+    Cell(u64),                                // + -，改写前当前格子的值
+    Move { cell: i64, virtual_pointer: i64 }, // > <，cell 为实际读写的格子的逻辑位置
+    In { cell: u64, byte: Option<u8> },       // ,，读入前格子的值和读到的字节
+    Out,                                      // .，撤销时按当前格子的值算出输出了几个字节
+}
+
+/// 执行一步之后的状态
+pub enum Step {
+    Next,
+    Err(RuntimeError),
+    End,
+}
+
+impl<I: Input, O: Output> Vm<I, O> {
+    pub fn new(program: Program, options: Options, input: I, output: O) -> Vm<I, O> {
+        Vm {
+            counts: vec![0; program.len()],
+            passes: vec![0; program.len()],
+            program,
+            program_counter: 0,
+            command_executed: 0,
+            tape: Tape::new(options),
+            eof: options.eof,
+            output_as_int: options.output_as_int,
+            input,
+            output,
+            history: VecDeque::new(),
+            history_limit: 0,
+            unread: Vec::new(),
+        }
+    }
+
+    /// 记录最近 `limit` 步的撤销信息，之后可以用 `step_back` 倒退，`limit` 为 0 时不记录
+    pub fn record_history(&mut self, limit: usize) {
+        self.history_limit = limit;
+        while self.history.len() > limit {
+            self.history.pop_front();
+        }
+    }
+
+    /// 还能撤销几步
+    pub fn history_len(&self) -> usize {
+        self.history.len()
+    }
+
+    /// 最近 `steps` 步里写过的格子，返回逻辑位置和是几步之前写的（0 为刚执行的一步）
+    ///
+    /// 从撤销记录里倒推，只能查到记录了的步数。
+    pub fn recent_writes(&self, steps: usize) -> Vec<(i64, usize)> {
+        let mut position = self.tape.pointer() as i64 + self.tape.origin();
+        let mut writes = Vec::new();
+        for (age, undo) in self.history.iter().rev().take(steps).enumerate() {
+            match undo.change {
+                Change::Move { cell, .. } => position = cell,
+                Change::Cell(_) | Change::In { .. } => writes.push((position, age)),
+                Change::Jump | Change::Out => (),
+            }
+        }
+        writes
+    }
+
+    /// 撤销 `,` 退回、还没有再次读入的输入个数
+    pub fn unread_len(&self) -> usize {
+        self.unread.len()
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    pub fn program_counter(&self) -> usize {
+        self.program_counter
+    }
+
+    pub fn command_executed(&self) -> u64 {
+        self.command_executed
+    }
+
+    /// 每条指令成功执行过的次数，下标与 `Program` 一致
+    pub fn counts(&self) -> &[u64] {
+        &self.counts
+    }
+
+    /// 每个 `[` 进入循环体的次数，即循环执行的轮数，其余指令为 0
+    ///
+    /// `]` 跳回 `[` 重新判断，所以不能用循环体里某条指令的次数代替。
+    pub fn passes(&self) -> &[u64] {
+        &self.passes
+    }
+
+    pub fn tape(&self) -> &Tape {
+        &self.tape
+    }
+
+    pub fn input(&self) -> &I {
+        &self.input
+    }
+
+    /// 可以借此继续往输入里追加数据
+    pub fn input_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    pub fn output(&self) -> &O {
+        &self.output
+    }
+
+    pub fn output_mut(&mut self) -> &mut O {
+        &mut self.output
+    }
+
+    /// 执行一条指令，程序已经结束时返回 `Step::End`
+    pub fn step(&mut self) -> Step {
+        if self.program_counter == self.program.len() {
+            return Step::End;
+        }
+        self.command()
+    }
+
+    /// 一直执行，直到 `stop` 返回 `true`、程序结束或出错
+    ///
+    /// `stop` 在每一步之前检查，因停下而返回时结果为 `Step::Next`。
+    pub fn run_until<F>(&mut self, mut stop: F) -> Step
+    where
+        F: FnMut(&Vm<I, O>) -> bool,
+    {
+        loop {
+            if stop(self) {
+                return Step::Next;
+            }
+            match self.step() {
+                Step::Next => (),
+                step => return step,
+            }
+        }
+    }
+
+    fn command(&mut self) -> Step {
+        let program_counter = self.program_counter;
+        let key = match self.program.get(program_counter) {
+            Some(key) => key,
+            None => unreachable!("program counter out of range"),
+        };
+        let change = match *key {
+            Key::Right | Key::Left => {
+                let offset = if let Key::Right = key { 1 } else { -1 };
+                let cell = self.tape.pointer() as i64 + self.tape.origin();
+                let virtual_pointer = self.tape.virtual_pointer();
+                if self.tape.shift(offset).is_err() {
+                    return Step::Err(self.error(RuntimeErrorKind::PointerOverflow));
+                }
+                self.program_counter += 1;
+                Change::Move { cell, virtual_pointer }
+            },
+            Key::Add | Key::Sub => {
+                let delta = if let Key::Add = key { 1 } else { -1 };
+                let cell = self.tape.get();
+                if self.tape.add(delta).is_err() {
+                    return Step::Err(self.error(RuntimeErrorKind::CellOverflow));
+                }
+                self.program_counter += 1;
+                Change::Cell(cell)
+            },
+            Key::In => {
+                let byte = match self.unread.pop() {
+                    Some(byte) => byte,
+                    None => match self.input.read() {
+                        Ok(byte) => byte,
+                        Err(err) => return Step::Err(self.error(RuntimeErrorKind::Input(err))),
+                    },
+                };
+                let (cell, max) = (self.tape.get(), self.tape.max());
+                store_cell(self.tape.current_mut(), byte, self.eof, max);
+                self.program_counter += 1;
+                Change::In { cell, byte }
+            },
+            Key::Out => {
+                if let Err(err) = write_cell(&mut self.output, self.tape.get(), self.output_as_int) {
+                    return Step::Err(self.error(RuntimeErrorKind::Output(err)));
+                }
+                self.program_counter += 1;
+                Change::Out
+            },
+            Key::If(index) => {
+                if self.tape.get() == 0 {
+                    self.program_counter = index;
+                } else {
+                    self.program_counter += 1;
+                    self.passes[program_counter] += 1;
+                }
+                Change::Jump
+            },
+            Key::Back(index) => {
+                if self.tape.get() != 0 {
+                    self.program_counter = index;
+                } else {
+                    self.program_counter += 1;
+                }
+                Change::Jump
+            },
+            Key::Debug => {
+                self.program_counter += 1;
+                Change::Jump
+            },
+        };
+        self.counts[program_counter] += 1;
+        self.command_executed += 1;
+        if self.history_limit > 0 {
+            if self.history.len() == self.history_limit {
+                self.history.pop_front();
+            }
+            self.history.push_back(Undo { program_counter, change });
+        }
+        Step::Next
+    }
+
+    fn error(&self, kind: RuntimeErrorKind) -> RuntimeError {
+        RuntimeError {
+            kind,
+            program_counter: self.program_counter,
+            pointer: self.tape.virtual_pointer(),
+            location: self.program.location(self.program_counter).unwrap_or_default(),
+        }
+    }
+}
+
+/// 输出存在内存里时才能撤销 `.`，所以只有这时能倒退
+impl<I: Input> Vm<I, Vec<u8>> {
+    /// 撤销上一步，没有记录可撤销时返回 `false`
+    pub fn step_back(&mut self) -> bool {
+        let undo = match self.history.pop_back() {
+            Some(undo) => undo,
+            None => return false,
+        };
+        match undo.change {
+            // `[` 之后落到下一条指令说明进入了循环体，`[]` 在解析时已经去掉，跳过时不会落到这里
+            Change::Jump => {
+                let entered = self.program_counter == undo.program_counter + 1;
+                if let (Some(Key::If(_)), true) = (self.program.get(undo.program_counter), entered) {
+                    self.passes[undo.program_counter] -= 1;
+                }
+            },
+            Change::Cell(value) => self.tape.set(value),
+            Change::Move { cell, virtual_pointer } => {
+                // Grow 向左扩展过的话 origin 变了，按逻辑位置换算下标
+                let pointer = (cell - self.tape.origin()) as usize;
+                self.tape.seek(pointer, virtual_pointer);
+            },
+            Change::In { cell, byte } => {
+                self.tape.set(cell);
+                self.unread.push(byte);
+            },
+            Change::Out => {
+                let len = if self.output_as_int {
+                    format!("{} ", self.tape.get()).len()
+                } else {
+                    1
+                };
+                self.output.truncate(self.output.len() - len);
+            },
+        }
+        self.program_counter = undo.program_counter;
+        self.counts[undo.program_counter] -= 1;
+        self.command_executed -= 1;
+        true
+    }
+}
+
+/// `,`：读入一个字节存到当前格子，输入结束时按 `Eof` 处理，`max` 为格子的最大值
+pub(crate) fn read_cell<I: Input + ?Sized>(input: &mut I, cell: &mut u64, eof: Eof, max: u64) -> io::Result<()> {
+    store_cell(cell, input.read()?, eof, max);
+    Ok(())
+}
+
+fn store_cell(cell: &mut u64, byte: Option<u8>, eof: Eof, max: u64) {
+    match (byte, eof) {
+        (Some(value), _) => *cell = value as u64,
+        (None, Eof::Unchanged) => (),
+        (None, Eof::Zero) => *cell = 0,
+        (None, Eof::Max) => *cell = max,
+    }
+}
+
+/// `.`：输出格子的值，按字节输出时只取最低 8 位
+pub(crate) fn write_cell<O: Output + ?Sized>(output: &mut O, value: u64, as_int: bool) -> io::Result<()> {
+    if as_int {
+        output.write(format!("{} ", value).as_bytes())
+    } else {
+        output.write(&[value as u8])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(source: &str, options: Options, input: &[u8]) -> Vm<VecDeque<u8>, Vec<u8>> {
+        let program = Program::from(source).unwrap();
+        Vm::new(program, options, VecDeque::from(input.to_vec()), Vec::new())
+    }
+
+    fn options(overflow: Overflow, tape_length: usize) -> Options {
+        let tape_length = NonZeroUsize::new(tape_length).unwrap();
+        Options { overflow, tape_length, ..Options::default() }
+    }
+
+    /// 跑到结束，返回实际读写的下标和逻辑位置
+    fn pointers(source: &str, options: Options) -> (usize, i64) {
+        let mut vm = load(source, options, &[]);
+        assert!(matches!(vm.run_until(|_| false), Step::End));
+        (vm.tape().pointer(), vm.tape().virtual_pointer())
+    }
+
+    #[test]
+    fn reads_input_and_writes_output() {
+        let mut vm = load(",[.-]", Options::default(), &[3]);
+        assert!(matches!(vm.run_until(|_| false), Step::End));
+        assert_eq!(vm.output(), &[3, 2, 1]);
+        assert_eq!(vm.command_executed(), 13);
+        assert!(matches!(vm.step(), Step::End));
+    }
+
+    #[test]
+    fn eof_policies() {
+        for (eof, cell) in [(Eof::Unchanged, 7), (Eof::Zero, 0), (Eof::Max, 255)] {
+            let mut vm = load("+++++++,", Options { eof, ..Options::default() }, &[]);
+            assert!(matches!(vm.run_until(|_| false), Step::End));
+            assert_eq!(vm.tape().get(), cell);
+        }
+    }
+
+    #[test]
+    fn output_as_int() {
+        let options = Options { output_as_int: true, cell_bits: CellBits::B16, ..Options::default() };
+        let mut vm = load("-.>+.", options, &[]);
+        assert!(matches!(vm.run_until(|_| false), Step::End));
+        assert_eq!(vm.output(), b"65535 1 ");
+    }
+
+    #[test]
+    fn block_stays_at_the_edges() {
+        assert_eq!(pointers("<<", options(Overflow::Block, 4)), (0, 0));
+        assert_eq!(pointers(">>>>>>", options(Overflow::Block, 4)), (3, 3));
+    }
+
+    #[test]
+    fn overflow_keeps_the_virtual_pointer() {
+        assert_eq!(pointers("<<", options(Overflow::Overflow, 4)), (0, -2));
+        assert_eq!(pointers(">>>>>>", options(Overflow::Overflow, 4)), (3, 6));
+        assert_eq!(pointers("<<>>>", options(Overflow::Overflow, 4)), (1, 1));
+    }
+
+    #[test]
+    fn loop_wraps_around() {
+        assert_eq!(pointers("<", options(Overflow::Loop, 4)), (3, 3));
+        assert_eq!(pointers(">>>>>", options(Overflow::Loop, 4)), (1, 1));
+    }
+
+    #[test]
+    fn grow_extends_the_tape() {
+        let options = Options { tape_limit: NonZeroUsize::new(4).unwrap(), ..options(Overflow::Grow, 1) };
+        let mut vm = load(">>>+", options, &[]);
+        assert!(matches!(vm.run_until(|_| false), Step::End));
+        assert_eq!(vm.tape().cells(), &[0, 0, 0, 1]);
+
+        let mut vm = load(">>>>", options, &[]);
+        match vm.run_until(|_| false) {
+            Step::Err(err) => {
+                assert!(matches!(err.kind, RuntimeErrorKind::PointerOverflow));
+                assert_eq!((err.program_counter, err.pointer), (3, 3));
+            },
+            _ => panic!("the tape grew past tape_limit"),
+        }
+    }
+
+    #[test]
+    fn grow_left() {
+        let options = Options {
+            tape_limit: NonZeroUsize::new(8).unwrap(),
+            grow_left: true,
+            ..options(Overflow::Grow, 1)
+        };
+        let mut vm = load("<<<+", options, &[]);
+        assert!(matches!(vm.run_until(|_| false), Step::End));
+        let tape = vm.tape();
+        assert_eq!(tape.virtual_pointer(), -3);
+        assert_eq!(tape.pointer() as i64 + tape.origin(), -3);
+        assert_eq!(tape.cells()[tape.pointer()], 1);
+
+        let options = Options { grow_left: false, ..options };
+        let mut vm = load("+<", options, &[]);
+        assert!(matches!(vm.run_until(|_| false), Step::Err(_)));
+    }
+
+    #[test]
+    fn fixed() {
+        let options = Options { tape_limit: NonZeroUsize::new(8).unwrap(), ..options(Overflow::Grow, 1) };
+        let fixed = options.fixed().unwrap();
+        assert!(matches!(fixed.overflow, Overflow::Exit));
+        assert_eq!(fixed.tape_length.get(), 8);
+
+        let options = Options { grow_left: true, ..options };
+        assert!(matches!(options.fixed(), Err(ConfigError::Unsupported { key, .. }) if key == "grow_left"));
+    }
+
+    #[test]
+    fn pointer_overflow_error() {
+        let mut vm = load("+>>\n>>", options(Overflow::Exit, 4), &[]);
+        match vm.run_until(|_| false) {
+            Step::Err(err) => {
+                assert!(matches!(err.kind, RuntimeErrorKind::PointerOverflow));
+                assert_eq!((err.program_counter, err.pointer), (4, 3));
+                assert_eq!((err.location.line, err.location.column), (2, 2));
+            },
+            _ => panic!("the pointer left the tape"),
+        }
+        // 出错的指令没有执行，程序计数器停在那里，也不计入执行次数
+        assert_eq!(vm.program_counter(), 4);
+        assert_eq!(vm.command_executed(), 4);
+        assert_eq!(vm.counts(), &[1, 1, 1, 1, 0]);
+
+        let mut vm = load("<", options(Overflow::Exit, 4), &[]);
+        assert!(matches!(vm.run_until(|_| false), Step::Err(err) if err.pointer == 0));
+    }
+
+    #[test]
+    fn cell_overflow_error() {
+        let options = Options { cell_overflow: CellOverflow::Exit, ..Options::default() };
+        let mut vm = load(">+-+--", options, &[]);
+        match vm.run_until(|_| false) {
+            Step::Err(err) => {
+                assert!(matches!(err.kind, RuntimeErrorKind::CellOverflow));
+                assert_eq!((err.program_counter, err.pointer), (5, 1));
+            },
+            _ => panic!("the cell went below zero"),
+        }
+        assert_eq!(vm.tape().get(), 0);
+
+        let options = Options { cell_overflow: CellOverflow::Saturate, ..Options::default() };
+        let mut vm = load("--->+++", options, &[]);
+        assert!(matches!(vm.run_until(|_| false), Step::End));
+        assert_eq!(&vm.tape().cells()[..2], &[0, 3]);
+    }
+
+    #[test]
+    fn input_error() {
+        struct Broken;
+
+        impl Input for Broken {
+            fn read(&mut self) -> io::Result<Option<u8>> {
+                Err(io::Error::other("broken"))
+            }
+        }
+
+        let program = Program::from(">,").unwrap();
+        let mut vm = Vm::new(program, Options::default(), Broken, Vec::new());
+        match vm.run_until(|_| false) {
+            Step::Err(err) => {
+                assert!(matches!(err.kind, RuntimeErrorKind::Input(_)));
+                assert_eq!((err.program_counter, err.pointer), (1, 1));
+            },
+            _ => panic!("the input failed"),
+        }
+    }
+
+    #[test]
+    fn step_back_restores_counts() {
+        let mut vm = load("++[>+[-]<-]", Options::default(), &[]);
+        vm.record_history(1000);
+        assert!(matches!(vm.run_until(|_| false), Step::End));
+        assert_eq!((vm.passes()[2], vm.passes()[5]), (2, 2));
+        while vm.step_back() {}
+        assert_eq!(vm.program_counter(), 0);
+        assert!(vm.counts().iter().chain(vm.passes()).all(|&count| count == 0));
+    }
+
+    #[test]
+    fn run_until_stops_before_the_step() {
+        let mut vm = load("+[+]-", Options::default(), &[]);
+        let step = vm.run_until(|vm| vm.tape().get() == 5);
+        assert!(matches!(step, Step::Next));
+        assert_eq!(vm.tape().get(), 5);
+        let pc = vm.program_counter();
+
+        // 条件已经满足时一步也不执行
+        assert!(matches!(vm.run_until(|vm| vm.tape().get() == 5), Step::Next));
+        assert_eq!(vm.program_counter(), pc);
+
+        assert!(matches!(vm.run_until(|vm| vm.program_counter() == 4), Step::Next));
+        assert_eq!(vm.tape().get(), 0);
+        assert!(matches!(vm.run_until(|_| false), Step::End));
+        assert_eq!(vm.tape().get(), 255);
+    }
+}
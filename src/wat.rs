@@ -0,0 +1,479 @@
+use std::fmt::Write;
+
+use crate::{
+    ir::{Ir, Op},
+    vm::Options,
+    CellBits, ConfigError, CellOverflow, Eof, Overflow,
+};
+
+/// 把 `Ir` 翻译成 WebAssembly 文本格式的模块
+///
+/// 纸带放在导出的线性内存 `memory` 开头，`,` 和 `.` 分别调用导入的
+/// `env.read_byte`（返回 -1 表示输入结束）和 `env.write_byte`。
+/// 导出的 `run` 正常结束时返回 0，`Overflow::Exit` 或 `CellOverflow::Exit` 越界时返回越界指令的下标加 1。
+/// 64 位的格子用 `i64` 运算，其余用 `i32`。
+pub fn compile(ir: &Ir, options: Options) -> Result<String, ConfigError> {
+    let options = options.fixed()?;
+    let len = options.tape_length.get();
+    let cell = Cell::new(options.cell_bits);
+    let pages = (len * cell.size).div_ceil(65536);
+    let wraps = matches!(options.cell_overflow, CellOverflow::Wrap);
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "\
+(module
+  (import \"env\" \"read_byte\" (func $read_byte (result i32)))
+  (import \"env\" \"write_byte\" (func $write_byte (param i32)))
+  (memory (export \"memory\") {pages})
+  (global $p (mut i32) (i32.const 0))
+  (global $vp (mut i32) (i32.const 0))
+  (global $command (mut i32) (i32.const 0))
+
+  ;; 移动指针，越界时按 Overflow 处理，返回非 0 表示需要退出
+  (func $shift (param $offset i32) (param $command i32) (result i32)
+    (local $target i32)
+    global.get $vp
+    local.get $offset
+    i32.add
+    local.set $target
+    local.get $target
+    i32.const {len}
+    i32.lt_u
+    if
+      local.get $target
+      global.set $vp
+      local.get $target
+      global.set $p
+      i32.const 0
+      return
+    end
+{}  )
+
+  ;; 指针未越界且 p + min ..= p + max 都在纸带内
+  (func $fits (param $min i32) (param $max i32) (result i32)
+    global.get $vp
+    global.get $p
+    i32.eq
+    global.get $p
+    local.get $min
+    i32.add
+    i32.const 0
+    i32.ge_s
+    i32.and
+    global.get $p
+    local.get $max
+    i32.add
+    i32.const {len}
+    i32.lt_s
+    i32.and
+  )
+",
+        shift_policy(options.overflow, len)
+    );
+    let ty = cell.ty;
+    if !wraps {
+        out.push_str(&add_function(options.cell_overflow, &cell));
+    }
+    if options.output_as_int {
+        let _ = write!(
+            out,
+            "
+  (func $put_digits (param $value {ty})
+    local.get $value
+    {ty}.const 10
+    {ty}.ge_u
+    if
+      local.get $value
+      {ty}.const 10
+      {ty}.div_u
+      call $put_digits
+    end
+    local.get $value
+    {ty}.const 10
+    {ty}.rem_u
+{}    i32.const 48
+    i32.add
+    call $write_byte
+  )
+",
+            cell.to_i32("    ")
+        );
+    }
+    let _ = write!(
+        out,
+        "
+  (func (export \"run\") (result i32)
+    (local $byte i32)
+    (local $count {ty})
+    (local $address i32)
+    block $exit
+"
+    );
+
+    let mut depth = 3;
+    for (index, op) in ir.ops().iter().enumerate() {
+        if let Op::Close(_) = op {
+            depth -= 2;
+        }
+        let indent = "  ".repeat(depth);
+        let mut line = |text: &str| {
+            for text in text.lines() {
+                let _ = writeln!(out, "{indent}{text}");
+            }
+        };
+        match op {
+            Op::Add(delta) if !wraps => {
+                let start = ir.span(index).map_or(0, |span| span.start);
+                line(&format!("i64.const {delta}"));
+                line(&format!("i32.const {start}"));
+                line("call $add");
+                line("br_if $exit");
+            },
+            Op::Add(delta) => {
+                line(&cell.address(""));
+                line(&cell.read(""));
+                line(&cell.constant(*delta));
+                line(&format!("{ty}.add"));
+                line(cell.store);
+            },
+            Op::Move(offset) => {
+                let start = ir.span(index).map_or(0, |span| span.start);
+                line(&format!("i32.const {offset}"));
+                line(&format!("i32.const {start}"));
+                line("call $shift");
+                line("br_if $exit");
+            },
+            Op::In => {
+                line("call $read_byte");
+                line("local.tee $byte");
+                line("i32.const 0");
+                if let Eof::Unchanged = options.eof {
+                    line("i32.ge_s");
+                    line("if");
+                    line(&cell.address("  "));
+                    line("  local.get $byte");
+                    if ty == "i64" {
+                        line("  i64.extend_i32_u");
+                    }
+                    line(&format!("  {}", cell.store));
+                    line("end");
+                } else {
+                    // -1 存进格子后正好是最大值
+                    line("i32.lt_s");
+                    line("if");
+                    line(&format!("  i32.const {}", if let Eof::Zero = options.eof { 0 } else { -1 }));
+                    line("  local.set $byte");
+                    line("end");
+                    line(&cell.address(""));
+                    line("local.get $byte");
+                    if ty == "i64" {
+                        line("i64.extend_i32_s");
+                    }
+                    line(cell.store);
+                }
+            },
+            Op::Out => {
+                line(&cell.read(""));
+                if options.output_as_int {
+                    line("call $put_digits");
+                    line("i32.const 32");
+                    line("call $write_byte");
+                } else {
+                    line(&cell.to_i32(""));
+                    line("call $write_byte");
+                }
+            },
+            Op::Open(_) => {
+                line(&format!("block $end{index}"));
+                line(&format!("  loop $body{index}"));
+                line(&cell.read("    "));
+                line(&format!("    {ty}.eqz"));
+                line(&format!("    br_if $end{index}"));
+                depth += 2;
+            },
+            Op::Close(open) => {
+                line(&format!("    br $body{open}"));
+                line("  end");
+                line("end");
+            },
+            Op::Clear => {
+                line(&cell.address(""));
+                line(&cell.constant(0));
+                line(cell.store);
+            },
+            Op::Scan { step, min, max } => {
+                line(&format!("block $found{index}"));
+                line(&format!("  loop $scan{index}"));
+                line(&cell.read("    "));
+                line(&format!("    {ty}.eqz"));
+                line(&format!("    br_if $found{index}"));
+                line(&format!("    i32.const {min}"));
+                line(&format!("    i32.const {max}"));
+                line("    call $fits");
+                line("    i32.eqz");
+                line(&format!("    br_if $found{index}"));
+                line("    global.get $p");
+                line(&format!("    i32.const {step}"));
+                line("    i32.add");
+                line("    global.set $p");
+                line("    global.get $p");
+                line("    global.set $vp");
+                line(&format!("    br $scan{index}"));
+                line("  end");
+                line("end");
+            },
+            Op::MulAdd { .. } if !wraps => (),
+            Op::MulAdd { targets, min, max } => {
+                line(&format!("i32.const {min}"));
+                line(&format!("i32.const {max}"));
+                line("call $fits");
+                line("if");
+                line(&cell.read("  "));
+                line("  local.set $count");
+                for (offset, factor) in targets {
+                    line("  global.get $p");
+                    line(&format!("  i32.const {offset}"));
+                    line("  i32.add");
+                    if cell.size > 1 {
+                        line(&format!("  i32.const {}", cell.size));
+                        line("  i32.mul");
+                    }
+                    line("  local.tee $address");
+                    line("  local.get $address");
+                    line(&format!("  {}", cell.load));
+                    line("  local.get $count");
+                    line(&format!("  {}", cell.constant(*factor)));
+                    line(&format!("  {ty}.mul"));
+                    line(&format!("  {ty}.add"));
+                    line(&format!("  {}", cell.store));
+                }
+                line(&cell.address("  "));
+                line(&format!("  {}", cell.constant(0)));
+                line(&format!("  {}", cell.store));
+                line("end");
+            },
+        }
+    }
+    out.push_str(
+        "\
+      i32.const 0
+      return
+    end
+    global.get $command
+    i32.const 1
+    i32.add
+  )
+)
+",
+    );
+    Ok(out)
+}
+
+/// 按格子宽度选用的类型和读写指令
+struct Cell {
+    size: usize,
+    max: u64,
+    ty: &'static str,
+    load: &'static str,
+    store: &'static str,
+}
+
+impl Cell {
+    fn new(cell_bits: CellBits) -> Cell {
+        let (ty, load, store) = match cell_bits {
+            CellBits::B8 => ("i32", "i32.load8_u", "i32.store8"),
+            CellBits::B16 => ("i32", "i32.load16_u", "i32.store16"),
+            CellBits::B32 => ("i32", "i32.load", "i32.store"),
+            CellBits::B64 => ("i64", "i64.load", "i64.store"),
+        };
+        Cell {
+            size: cell_bits.bits() as usize / 8,
+            max: cell_bits.max(),
+            ty,
+            load,
+            store,
+        }
+    }
+
+    /// 当前格子在线性内存中的地址
+    fn address(&self, indent: &str) -> String {
+        if self.size == 1 {
+            format!("{indent}global.get $p")
+        } else {
+            format!("{indent}global.get $p\n{indent}i32.const {}\n{indent}i32.mul", self.size)
+        }
+    }
+
+    /// 读出当前格子的值
+    fn read(&self, indent: &str) -> String {
+        format!("{}\n{indent}{}", self.address(indent), self.load)
+    }
+
+    fn constant(&self, value: i64) -> String {
+        if self.ty == "i64" {
+            format!("i64.const {value}")
+        } else {
+            format!("i32.const {}", value as i32)
+        }
+    }
+
+    /// 把栈顶的格子值转成 `i32`，本来就是 `i32` 时什么也不做
+    fn to_i32(&self, indent: &str) -> String {
+        if self.ty == "i64" {
+            format!("{indent}i32.wrap_i64\n")
+        } else {
+            String::new()
+        }
+    }
+}
+
+/// 不回绕时加减当前格子的 `$add`，返回非 0 表示需要退出
+fn add_function(cell_overflow: CellOverflow, cell: &Cell) -> String {
+    let max = cell.max;
+    // 越过上界时 $room 为还能加的量，越过下界时 $value 为还能减的量
+    let (over, under) = match cell_overflow {
+        CellOverflow::Saturate => (
+            format!("i64.const {max}\nlocal.set $value"),
+            "i64.const 0\nlocal.set $value".to_string(),
+        ),
+        _ => (
+            "local.get $command\nlocal.get $room\ni32.wrap_i64\ni32.add\nglobal.set $command\ni32.const 1\nreturn"
+                .to_string(),
+            "local.get $command\nlocal.get $value\ni32.wrap_i64\ni32.add\nglobal.set $command\ni32.const 1\nreturn"
+                .to_string(),
+        ),
+    };
+    let nest = |text: &str| text.lines().map(|line| format!("        {line}\n")).collect::<String>();
+    let extend = if cell.ty == "i64" { "" } else { "    i64.extend_i32_u\n" };
+    let wrap = if cell.ty == "i64" { "" } else { "    i32.wrap_i64\n" };
+    format!(
+        "
+  ;; 按 CellOverflow 加减当前格子，返回非 0 表示需要退出
+  (func $add (param $delta i64) (param $command i32) (result i32)
+    (local $value i64)
+    (local $room i64)
+{}
+{extend}    local.set $value
+    local.get $delta
+    i64.const 0
+    i64.lt_s
+    if
+      i64.const 0
+      local.get $delta
+      i64.sub
+      local.set $room
+      local.get $room
+      local.get $value
+      i64.gt_u
+      if
+{}      else
+        local.get $value
+        local.get $room
+        i64.sub
+        local.set $value
+      end
+    else
+      i64.const {max}
+      local.get $value
+      i64.sub
+      local.set $room
+      local.get $delta
+      local.get $room
+      i64.gt_u
+      if
+{}      else
+        local.get $value
+        local.get $delta
+        i64.add
+        local.set $value
+      end
+    end
+{}
+    local.get $value
+{wrap}    {}
+    i32.const 0
+  )
+",
+        cell.read("    "),
+        nest(&under),
+        nest(&over),
+        cell.address("    "),
+        cell.store
+    )
+}
+
+/// `$shift` 中越界之后的部分
+fn shift_policy(overflow: Overflow, len: usize) -> String {
+    let last = len - 1;
+    let body = match overflow {
+        Overflow::Block => format!(
+            "\
+i32.const 0
+i32.const {last}
+local.get $target
+i32.const 0
+i32.lt_s
+select
+local.tee $target
+global.set $vp
+local.get $target
+global.set $p"
+        ),
+        Overflow::Overflow => format!(
+            "\
+local.get $target
+global.set $vp
+i32.const 0
+i32.const {last}
+local.get $target
+i32.const 0
+i32.lt_s
+select
+global.set $p"
+        ),
+        Overflow::Loop => format!(
+            "\
+local.get $target
+i32.const {len}
+i32.rem_s
+local.tee $target
+i32.const 0
+i32.lt_s
+if
+  local.get $target
+  i32.const {len}
+  i32.add
+  local.set $target
+end
+local.get $target
+global.set $vp
+local.get $target
+global.set $p"
+        ),
+        Overflow::Exit | Overflow::Grow => format!(
+            "\
+local.get $command
+i32.const {last}
+global.get $vp
+i32.sub
+global.get $vp
+local.get $offset
+i32.const 0
+i32.gt_s
+select
+i32.add
+global.set $command
+i32.const 1
+return"
+        ),
+    };
+    let mut out = String::new();
+    for line in body.lines() {
+        let _ = writeln!(out, "    {line}");
+    }
+    if !matches!(overflow, Overflow::Exit | Overflow::Grow) {
+        out.push_str("    i32.const 0\n");
+    }
+    out
+}
@@ -0,0 +1,174 @@
+use std::{
+    collections::VecDeque,
+    env, fs,
+    io::Write,
+    num::NonZeroUsize,
+    process::{self, Command, Stdio},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use brain_fxxker::{asm, testing, CellBits, CellOverflow, Eof, Ir, Options, Overflow, Program};
+
+const OVERFLOWS: [Overflow; 5] = [Overflow::Block, Overflow::Overflow, Overflow::Loop, Overflow::Exit, Overflow::Grow];
+const EXAMPLES: [&str; 4] = [
+    "examples/hello_world.bf",
+    "examples/hello_world_ksabry.bf",
+    "examples/overflow.bf",
+    "examples/read_write.bf",
+];
+
+fn compile(path: &str, options: Options) -> String {
+    let source = fs::read_to_string(path).unwrap();
+    asm::compile(&Ir::new(Program::from(&source).unwrap()), options).unwrap()
+}
+
+fn options(overflow: Overflow, tape_length: usize) -> Options {
+    let tape_length = NonZeroUsize::new(tape_length).unwrap();
+    Options { overflow, tape_length, ..Options::default() }
+}
+
+#[test]
+fn hello_world() {
+    let asm = compile("examples/hello_world.bf", Options::default());
+    assert_eq!(asm, include_str!("golden/hello_world.s"));
+}
+
+#[test]
+fn read_write_with_saturate_and_int_output() {
+    let options = Options {
+        cell_bits: CellBits::B16,
+        cell_overflow: CellOverflow::Saturate,
+        eof: Eof::Zero,
+        output_as_int: true,
+        ..Options::default()
+    };
+    let asm = compile("examples/read_write.bf", options);
+    assert_eq!(asm, include_str!("golden/read_write.s"));
+}
+
+#[test]
+fn overflow_with_exit() {
+    let options = Options {
+        cell_bits: CellBits::B32,
+        cell_overflow: CellOverflow::Exit,
+        ..options(Overflow::Exit, 8)
+    };
+    let asm = compile("examples/overflow.bf", options);
+    assert_eq!(asm, include_str!("golden/overflow.s"));
+}
+
+#[test]
+fn overflow_with_grow() {
+    let options = Options {
+        cell_bits: CellBits::B64,
+        tape_limit: NonZeroUsize::new(16).unwrap(),
+        ..options(Overflow::Grow, 1)
+    };
+    let asm = compile("examples/overflow.bf", options);
+    assert_eq!(asm, include_str!("golden/overflow_grow.s"));
+}
+
+/// 不在 x86-64 Linux 上或者没有 as、ld 时跳过需要汇编运行的测试
+fn has_binutils() -> bool {
+    let native = cfg!(all(target_arch = "x86_64", target_os = "linux"));
+    let found = ["as", "ld"].iter().all(|tool| Command::new(tool).arg("--version").output().is_ok());
+    if !(native && found) {
+        eprintln!("as and ld for x86-64 Linux not found, skipping");
+    }
+    native && found
+}
+
+/// 用 as 和 ld 把生成的汇编做成可执行文件并运行，`input` 从标准输入传入
+fn run(source: &str, input: &[u8]) -> process::Output {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let name = format!("brain_fxxker-asm-{}-{}", process::id(), COUNT.fetch_add(1, Ordering::Relaxed));
+    let dir = env::temp_dir().join(name);
+    fs::create_dir_all(&dir).unwrap();
+    let (path, object, binary) = (dir.join("main.s"), dir.join("main.o"), dir.join("main"));
+    fs::write(&path, source).unwrap();
+    let status = Command::new("as").arg(&path).arg("-o").arg(&object).status().unwrap();
+    assert!(status.success(), "as failed on\n{source}");
+    let status = Command::new("ld").arg(&object).arg("-o").arg(&binary).status().unwrap();
+    assert!(status.success(), "ld failed on\n{source}");
+    let mut child = Command::new(&binary)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // 程序可能不读完输入就退出
+    let _ = child.stdin.take().unwrap().write_all(input);
+    let output = child.wait_with_output().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    output
+}
+
+/// 分别运行汇编出的程序和用 `Ir::run` 执行，比较输出和退出码，出错时 stderr 里要有同样的指令下标和指针位置
+fn compare(source: &str, options: Options, input: &[u8]) {
+    let ir = Ir::new(Program::from(source).unwrap());
+    let mut expected_output = Vec::new();
+    let expected = ir.run(options, &mut VecDeque::from(input.to_vec()), &mut expected_output);
+    let output = run(&asm::compile(&ir, options).unwrap(), input);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.stdout, expected_output, "{source}");
+    match expected {
+        Ok(_) => assert_eq!(output.status.code(), Some(0), "{source}: {stderr}"),
+        Err(err) => {
+            assert_eq!(output.status.code(), Some(4), "{source}");
+            let place = format!("(command {}, pointer {})", err.program_counter, err.pointer);
+            assert!(stderr.contains(&place), "{source}: expected {place} in {stderr}");
+        },
+    }
+}
+
+fn compare_if_ends(source: &str, options: Options, input: &[u8]) {
+    if testing::halts(source, options) {
+        compare(source, options, input);
+    }
+}
+
+#[test]
+fn examples_run_like_the_interpreter() {
+    if !has_binutils() {
+        return;
+    }
+    for path in EXAMPLES {
+        let source = fs::read_to_string(path).unwrap();
+        for cell_bits in [CellBits::B8, CellBits::B16, CellBits::B32, CellBits::B64] {
+            for cell_overflow in [CellOverflow::Wrap, CellOverflow::Saturate, CellOverflow::Exit] {
+                let options = Options { cell_bits, cell_overflow, ..options(Overflow::Exit, 30000) };
+                compare_if_ends(&source, options, b"abcdefghij");
+            }
+        }
+        for overflow in OVERFLOWS {
+            let options = Options { tape_limit: NonZeroUsize::new(16).unwrap(), ..options(overflow, 8) };
+            compare_if_ends(&source, options, b"abc");
+        }
+    }
+}
+
+#[test]
+fn read_write_runs_like_the_interpreter() {
+    if !has_binutils() {
+        return;
+    }
+    let source = fs::read_to_string("examples/read_write.bf").unwrap();
+    for eof in [Eof::Unchanged, Eof::Zero, Eof::Max] {
+        for output_as_int in [false, true] {
+            let options = Options { eof, output_as_int, cell_bits: CellBits::B16, ..Options::default() };
+            compare(&source, options, b"abc");
+        }
+    }
+}
+
+#[test]
+fn fallback_near_the_edges() {
+    if !has_binutils() {
+        return;
+    }
+    for overflow in OVERFLOWS {
+        for source in testing::edge_programs() {
+            compare_if_ends(&source, options(overflow, 4), &[]);
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use std::{
+    collections::VecDeque,
+    env, fs,
+    io::Write,
+    num::NonZeroUsize,
+    process::{self, Command, Stdio},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use brain_fxxker::{c, testing, CellBits, CellOverflow, Eof, Ir, Options, Overflow, Program};
+
+const OVERFLOWS: [Overflow; 5] = [Overflow::Block, Overflow::Overflow, Overflow::Loop, Overflow::Exit, Overflow::Grow];
+const EXAMPLES: [&str; 4] = [
+    "examples/hello_world.bf",
+    "examples/hello_world_ksabry.bf",
+    "examples/overflow.bf",
+    "examples/read_write.bf",
+];
+
+fn compile(path: &str, options: Options) -> String {
+    let source = fs::read_to_string(path).unwrap();
+    c::compile(&Ir::new(Program::from(&source).unwrap()), options).unwrap()
+}
+
+fn options(overflow: Overflow, tape_length: usize) -> Options {
+    let tape_length = NonZeroUsize::new(tape_length).unwrap();
+    Options { overflow, tape_length, ..Options::default() }
+}
+
+#[test]
+fn hello_world() {
+    let c = compile("examples/hello_world.bf", Options::default());
+    assert_eq!(c, include_str!("golden/hello_world.c"));
+}
+
+#[test]
+fn read_write_with_saturate_and_int_output() {
+    let options = Options {
+        cell_bits: CellBits::B16,
+        cell_overflow: CellOverflow::Saturate,
+        eof: Eof::Zero,
+        output_as_int: true,
+        ..Options::default()
+    };
+    let c = compile("examples/read_write.bf", options);
+    assert_eq!(c, include_str!("golden/read_write.c"));
+}
+
+#[test]
+fn overflow_with_exit() {
+    let options = Options {
+        cell_bits: CellBits::B32,
+        cell_overflow: CellOverflow::Exit,
+        ..options(Overflow::Exit, 8)
+    };
+    let c = compile("examples/overflow.bf", options);
+    assert_eq!(c, include_str!("golden/overflow.c"));
+}
+
+#[test]
+fn overflow_with_grow() {
+    let options = Options {
+        cell_bits: CellBits::B64,
+        tape_limit: NonZeroUsize::new(16).unwrap(),
+        ..options(Overflow::Grow, 1)
+    };
+    let c = compile("examples/overflow.bf", options);
+    assert_eq!(c, include_str!("golden/overflow_grow.c"));
+}
+
+/// 没有 cc 时跳过需要编译运行的测试
+fn has_cc() -> bool {
+    let found = Command::new("cc").arg("--version").output().is_ok();
+    if !found {
+        eprintln!("cc not found, skipping");
+    }
+    found
+}
+
+/// 用 cc 编译生成的 C 源码并运行，`input` 从标准输入传入
+fn run(source: &str, input: &[u8]) -> process::Output {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let name = format!("brain_fxxker-c-{}-{}", process::id(), COUNT.fetch_add(1, Ordering::Relaxed));
+    let dir = env::temp_dir().join(name);
+    fs::create_dir_all(&dir).unwrap();
+    let (path, binary) = (dir.join("main.c"), dir.join("main"));
+    fs::write(&path, source).unwrap();
+    let status = Command::new("cc").arg("-o").arg(&binary).arg(&path).status().unwrap();
+    assert!(status.success(), "cc failed on\n{source}");
+    let mut child = Command::new(&binary)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // 程序可能不读完输入就退出
+    let _ = child.stdin.take().unwrap().write_all(input);
+    let output = child.wait_with_output().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    output
+}
+
+/// 分别编译运行生成的 C 程序和用 `Ir::run` 执行，比较输出和退出码，出错时 stderr 里要有同样的指令下标和指针位置
+fn compare(source: &str, options: Options, input: &[u8]) {
+    let ir = Ir::new(Program::from(source).unwrap());
+    let mut expected_output = Vec::new();
+    let expected = ir.run(options, &mut VecDeque::from(input.to_vec()), &mut expected_output);
+    let output = run(&c::compile(&ir, options).unwrap(), input);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.stdout, expected_output, "{source}");
+    match expected {
+        Ok(_) => assert_eq!(output.status.code(), Some(0), "{source}: {stderr}"),
+        Err(err) => {
+            assert_eq!(output.status.code(), Some(4), "{source}");
+            let place = format!("(command {}, pointer {})", err.program_counter, err.pointer);
+            assert!(stderr.contains(&place), "{source}: expected {place} in {stderr}");
+        },
+    }
+}
+
+fn compare_if_ends(source: &str, options: Options, input: &[u8]) {
+    if testing::halts(source, options) {
+        compare(source, options, input);
+    }
+}
+
+#[test]
+fn examples_run_like_the_interpreter() {
+    if !has_cc() {
+        return;
+    }
+    for path in EXAMPLES {
+        let source = fs::read_to_string(path).unwrap();
+        for cell_bits in [CellBits::B8, CellBits::B16, CellBits::B32, CellBits::B64] {
+            for cell_overflow in [CellOverflow::Wrap, CellOverflow::Saturate, CellOverflow::Exit] {
+                let options = Options { cell_bits, cell_overflow, ..options(Overflow::Exit, 30000) };
+                compare_if_ends(&source, options, b"abcdefghij");
+            }
+        }
+        for overflow in OVERFLOWS {
+            let options = Options { tape_limit: NonZeroUsize::new(16).unwrap(), ..options(overflow, 8) };
+            compare_if_ends(&source, options, b"abc");
+        }
+    }
+}
+
+#[test]
+fn read_write_runs_like_the_interpreter() {
+    if !has_cc() {
+        return;
+    }
+    let source = fs::read_to_string("examples/read_write.bf").unwrap();
+    for eof in [Eof::Unchanged, Eof::Zero, Eof::Max] {
+        for output_as_int in [false, true] {
+            let options = Options { eof, output_as_int, cell_bits: CellBits::B16, ..Options::default() };
+            compare(&source, options, b"abc");
+        }
+    }
+}
+
+#[test]
+fn fallback_near_the_edges() {
+    if !has_cc() {
+        return;
+    }
+    for overflow in [Overflow::Loop, Overflow::Exit] {
+        for source in testing::edge_programs() {
+            compare_if_ends(&source, options(overflow, 4), &[]);
+        }
+    }
+}
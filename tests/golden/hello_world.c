@@ -0,0 +1,102 @@
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#define LEN 64L
+
+static uint8_t tape[LEN];
+static long p = 0;  /* 实际读写的格子 */
+static long vp = 0; /* 指针的逻辑位置 */
+
+static void shift(long offset, long command) {
+    (void)command;
+    vp += offset;
+    if (vp < 0) vp = 0;
+    if (vp >= LEN) vp = LEN - 1;
+    p = vp;
+}
+
+#define MAX 255ULL
+
+static int fits(long min, long max) {
+    return vp == p && p + min >= 0 && p + max < LEN;
+}
+
+int main(void) {
+    tape[p] += 8;
+    if (fits(0, 1)) {
+        tape[p + 1] += tape[p] * 9ULL;
+        tape[p] = 0;
+    }
+    while (tape[p]) {
+        shift(1, 9);
+        tape[p] += 9;
+        shift(-1, 19);
+        tape[p] -= 1;
+    }
+    shift(1, 22);
+    putchar(tape[p]);
+    shift(2, 24);
+    tape[p] += 10;
+    if (fits(-1, 0)) {
+        tape[p - 1] += tape[p] * 10ULL;
+        tape[p] = 0;
+    }
+    while (tape[p]) {
+        shift(-1, 37);
+        tape[p] += 10;
+        shift(1, 48);
+        tape[p] -= 1;
+    }
+    shift(-1, 51);
+    tape[p] += 1;
+    putchar(tape[p]);
+    tape[p] += 7;
+    putchar(tape[p]);
+    putchar(tape[p]);
+    tape[p] += 3;
+    putchar(tape[p]);
+    shift(2, 67);
+    tape[p] += 4;
+    if (fits(-1, 0)) {
+        tape[p - 1] += tape[p] * 11ULL;
+        tape[p] = 0;
+    }
+    while (tape[p]) {
+        shift(-1, 74);
+        tape[p] += 11;
+        shift(1, 86);
+        tape[p] -= 1;
+    }
+    shift(-1, 89);
+    putchar(tape[p]);
+    tape[p] -= 12;
+    putchar(tape[p]);
+    shift(-3, 104);
+    tape[p] += 3;
+    if (fits(0, 1)) {
+        tape[p + 1] += tape[p] * 5ULL;
+        tape[p] = 0;
+    }
+    while (tape[p]) {
+        shift(1, 111);
+        tape[p] += 5;
+        shift(-1, 117);
+        tape[p] -= 1;
+    }
+    shift(1, 120);
+    putchar(tape[p]);
+    shift(1, 122);
+    putchar(tape[p]);
+    tape[p] += 3;
+    putchar(tape[p]);
+    tape[p] -= 6;
+    putchar(tape[p]);
+    tape[p] -= 8;
+    putchar(tape[p]);
+    shift(1, 144);
+    tape[p] += 1;
+    putchar(tape[p]);
+    fflush(stdout);
+    return 0;
+}
@@ -0,0 +1,428 @@
+.intel_syntax noprefix
+    .globl _start
+
+    .bss
+tape:
+    .zero 64
+outbuf:
+    .zero 4096
+numbuf:
+    .zero 32
+
+    .section .rodata
+overflow_message:
+    .ascii "Overflow exit. (command "
+cell_message:
+    .ascii "Cell overflow exit. (command "
+minus_message:
+    .ascii "-"
+pointer_message:
+    .ascii ", pointer "
+end_message:
+    .ascii ")\n"
+input_message:
+    .ascii "Failed to read input.\n"
+output_message:
+    .ascii "Failed to write output.\n"
+
+    .text
+_start:
+    lea rbx, [rip + tape]
+    xor r12d, r12d
+    xor r13d, r13d
+    xor r14d, r14d
+    add byte ptr [rbx + r12], 8
+cmp r13, r12
+    jne .L1_done
+    lea rax, [r12 + 0]
+    test rax, rax
+    js .L1_done
+    lea rax, [r12 + 1]
+    cmp rax, 64
+    jge .L1_done
+    movzx eax, byte ptr [rbx + r12]
+    imul rcx, rax, 9
+    add byte ptr [rbx + r12 + 1], cl
+    mov byte ptr [rbx + r12], 0
+.L1_done:
+    cmp byte ptr [rbx + r12], 0
+    je .L7_end
+.L2_body:
+lea rax, [r13 + 1]
+    cmp rax, 64
+    jae .L3_slow
+    mov r13, rax
+    mov r12, rax
+    jmp .L3_done
+.L3_slow:
+    mov rsi, 9
+    call shift
+.L3_done:
+    add byte ptr [rbx + r12], 9
+lea rax, [r13 - 1]
+    cmp rax, 64
+    jae .L5_slow
+    mov r13, rax
+    mov r12, rax
+    jmp .L5_done
+.L5_slow:
+    mov rsi, 19
+    call shift
+.L5_done:
+    sub byte ptr [rbx + r12], 1
+    cmp byte ptr [rbx + r12], 0
+    jne .L2_body
+.L7_end:
+lea rax, [r13 + 1]
+    cmp rax, 64
+    jae .L8_slow
+    mov r13, rax
+    mov r12, rax
+    jmp .L8_done
+.L8_slow:
+    mov rsi, 22
+    call shift
+.L8_done:
+    movzx eax, byte ptr [rbx + r12]
+    call put_byte
+lea rax, [r13 + 2]
+    cmp rax, 64
+    jae .L10_slow
+    mov r13, rax
+    mov r12, rax
+    jmp .L10_done
+.L10_slow:
+    mov rsi, 24
+    call shift
+.L10_done:
+    add byte ptr [rbx + r12], 10
+cmp r13, r12
+    jne .L12_done
+    lea rax, [r12 - 1]
+    test rax, rax
+    js .L12_done
+    lea rax, [r12 + 0]
+    cmp rax, 64
+    jge .L12_done
+    movzx eax, byte ptr [rbx + r12]
+    imul rcx, rax, 10
+    add byte ptr [rbx + r12 - 1], cl
+    mov byte ptr [rbx + r12], 0
+.L12_done:
+    cmp byte ptr [rbx + r12], 0
+    je .L18_end
+.L13_body:
+lea rax, [r13 - 1]
+    cmp rax, 64
+    jae .L14_slow
+    mov r13, rax
+    mov r12, rax
+    jmp .L14_done
+.L14_slow:
+    mov rsi, 37
+    call shift
+.L14_done:
+    add byte ptr [rbx + r12], 10
+lea rax, [r13 + 1]
+    cmp rax, 64
+    jae .L16_slow
+    mov r13, rax
+    mov r12, rax
+    jmp .L16_done
+.L16_slow:
+    mov rsi, 48
+    call shift
+.L16_done:
+    sub byte ptr [rbx + r12], 1
+    cmp byte ptr [rbx + r12], 0
+    jne .L13_body
+.L18_end:
+lea rax, [r13 - 1]
+    cmp rax, 64
+    jae .L19_slow
+    mov r13, rax
+    mov r12, rax
+    jmp .L19_done
+.L19_slow:
+    mov rsi, 51
+    call shift
+.L19_done:
+    add byte ptr [rbx + r12], 1
+    movzx eax, byte ptr [rbx + r12]
+    call put_byte
+    add byte ptr [rbx + r12], 7
+    movzx eax, byte ptr [rbx + r12]
+    call put_byte
+    movzx eax, byte ptr [rbx + r12]
+    call put_byte
+    add byte ptr [rbx + r12], 3
+    movzx eax, byte ptr [rbx + r12]
+    call put_byte
+lea rax, [r13 + 2]
+    cmp rax, 64
+    jae .L27_slow
+    mov r13, rax
+    mov r12, rax
+    jmp .L27_done
+.L27_slow:
+    mov rsi, 67
+    call shift
+.L27_done:
+    add byte ptr [rbx + r12], 4
+cmp r13, r12
+    jne .L29_done
+    lea rax, [r12 - 1]
+    test rax, rax
+    js .L29_done
+    lea rax, [r12 + 0]
+    cmp rax, 64
+    jge .L29_done
+    movzx eax, byte ptr [rbx + r12]
+    imul rcx, rax, 11
+    add byte ptr [rbx + r12 - 1], cl
+    mov byte ptr [rbx + r12], 0
+.L29_done:
+    cmp byte ptr [rbx + r12], 0
+    je .L35_end
+.L30_body:
+lea rax, [r13 - 1]
+    cmp rax, 64
+    jae .L31_slow
+    mov r13, rax
+    mov r12, rax
+    jmp .L31_done
+.L31_slow:
+    mov rsi, 74
+    call shift
+.L31_done:
+    add byte ptr [rbx + r12], 11
+lea rax, [r13 + 1]
+    cmp rax, 64
+    jae .L33_slow
+    mov r13, rax
+    mov r12, rax
+    jmp .L33_done
+.L33_slow:
+    mov rsi, 86
+    call shift
+.L33_done:
+    sub byte ptr [rbx + r12], 1
+    cmp byte ptr [rbx + r12], 0
+    jne .L30_body
+.L35_end:
+lea rax, [r13 - 1]
+    cmp rax, 64
+    jae .L36_slow
+    mov r13, rax
+    mov r12, rax
+    jmp .L36_done
+.L36_slow:
+    mov rsi, 89
+    call shift
+.L36_done:
+    movzx eax, byte ptr [rbx + r12]
+    call put_byte
+    sub byte ptr [rbx + r12], 12
+    movzx eax, byte ptr [rbx + r12]
+    call put_byte
+lea rax, [r13 - 3]
+    cmp rax, 64
+    jae .L40_slow
+    mov r13, rax
+    mov r12, rax
+    jmp .L40_done
+.L40_slow:
+    mov rsi, 104
+    call shift
+.L40_done:
+    add byte ptr [rbx + r12], 3
+cmp r13, r12
+    jne .L42_done
+    lea rax, [r12 + 0]
+    test rax, rax
+    js .L42_done
+    lea rax, [r12 + 1]
+    cmp rax, 64
+    jge .L42_done
+    movzx eax, byte ptr [rbx + r12]
+    imul rcx, rax, 5
+    add byte ptr [rbx + r12 + 1], cl
+    mov byte ptr [rbx + r12], 0
+.L42_done:
+    cmp byte ptr [rbx + r12], 0
+    je .L48_end
+.L43_body:
+lea rax, [r13 + 1]
+    cmp rax, 64
+    jae .L44_slow
+    mov r13, rax
+    mov r12, rax
+    jmp .L44_done
+.L44_slow:
+    mov rsi, 111
+    call shift
+.L44_done:
+    add byte ptr [rbx + r12], 5
+lea rax, [r13 - 1]
+    cmp rax, 64
+    jae .L46_slow
+    mov r13, rax
+    mov r12, rax
+    jmp .L46_done
+.L46_slow:
+    mov rsi, 117
+    call shift
+.L46_done:
+    sub byte ptr [rbx + r12], 1
+    cmp byte ptr [rbx + r12], 0
+    jne .L43_body
+.L48_end:
+lea rax, [r13 + 1]
+    cmp rax, 64
+    jae .L49_slow
+    mov r13, rax
+    mov r12, rax
+    jmp .L49_done
+.L49_slow:
+    mov rsi, 120
+    call shift
+.L49_done:
+    movzx eax, byte ptr [rbx + r12]
+    call put_byte
+lea rax, [r13 + 1]
+    cmp rax, 64
+    jae .L51_slow
+    mov r13, rax
+    mov r12, rax
+    jmp .L51_done
+.L51_slow:
+    mov rsi, 122
+    call shift
+.L51_done:
+    movzx eax, byte ptr [rbx + r12]
+    call put_byte
+    add byte ptr [rbx + r12], 3
+    movzx eax, byte ptr [rbx + r12]
+    call put_byte
+    sub byte ptr [rbx + r12], 6
+    movzx eax, byte ptr [rbx + r12]
+    call put_byte
+    sub byte ptr [rbx + r12], 8
+    movzx eax, byte ptr [rbx + r12]
+    call put_byte
+lea rax, [r13 + 1]
+    cmp rax, 64
+    jae .L59_slow
+    mov r13, rax
+    mov r12, rax
+    jmp .L59_done
+.L59_slow:
+    mov rsi, 144
+    call shift
+.L59_done:
+    add byte ptr [rbx + r12], 1
+    movzx eax, byte ptr [rbx + r12]
+    call put_byte
+call flush
+    mov eax, 60
+    xor edi, edi
+    syscall
+
+shift:
+xor ecx, ecx
+    test rax, rax
+    cmovs rax, rcx
+    mov rcx, 63
+    cmp rax, rcx
+    cmovg rax, rcx
+    mov r13, rax
+    mov r12, rax
+    ret
+
+put_byte:
+    lea rcx, [rip + outbuf]
+    mov byte ptr [rcx + r14], al
+    inc r14
+    cmp r14, 4096
+    jae flush
+    ret
+
+put_int:
+    call format_int
+.Lput_digit:
+    mov al, byte ptr [rsi]
+    push rsi
+    push rdx
+    call put_byte
+    pop rdx
+    pop rsi
+    inc rsi
+    dec rdx
+    jnz .Lput_digit
+    mov al, 32
+    jmp put_byte
+
+# 把 rax 转成十进制放进 numbuf，返回 rsi 为首地址，rdx 为长度
+format_int:
+    lea rsi, [rip + numbuf + 32]
+    mov rcx, 10
+.Lformat_digit:
+    xor edx, edx
+    div rcx
+    add dl, 48
+    dec rsi
+    mov byte ptr [rsi], dl
+    test rax, rax
+    jnz .Lformat_digit
+    lea rdx, [rip + numbuf + 32]
+    sub rdx, rsi
+    ret
+
+flush:
+    xor esi, esi
+.Lflush_more:
+    cmp rsi, r14
+    jae .Lflush_done
+    push rsi
+    mov eax, 1
+    mov edi, 1
+    lea rcx, [rip + outbuf]
+    mov rdx, r14
+    sub rdx, rsi
+    add rsi, rcx
+    syscall
+    pop rsi
+    test rax, rax
+    js output_error
+    add rsi, rax
+    jmp .Lflush_more
+.Lflush_done:
+    xor r14d, r14d
+    ret
+
+error_write:
+    mov eax, 1
+    mov edi, 2
+    syscall
+    ret
+
+error_int:
+    call format_int
+    jmp error_write
+
+input_error:
+    call flush
+    lea rsi, [rip + input_message]
+    mov edx, 22
+    jmp error_exit
+
+output_error:
+    xor r14d, r14d
+    lea rsi, [rip + output_message]
+    mov edx, 24
+
+error_exit:
+    call error_write
+    mov eax, 60
+    mov edi, 4
+    syscall
@@ -0,0 +1,422 @@
+(module
+  (import "env" "read_byte" (func $read_byte (result i32)))
+  (import "env" "write_byte" (func $write_byte (param i32)))
+  (memory (export "memory") 1)
+  (global $p (mut i32) (i32.const 0))
+  (global $vp (mut i32) (i32.const 0))
+  (global $command (mut i32) (i32.const 0))
+
+  ;; 移动指针，越界时按 Overflow 处理，返回非 0 表示需要退出
+  (func $shift (param $offset i32) (param $command i32) (result i32)
+    (local $target i32)
+    global.get $vp
+    local.get $offset
+    i32.add
+    local.set $target
+    local.get $target
+    i32.const 64
+    i32.lt_u
+    if
+      local.get $target
+      global.set $vp
+      local.get $target
+      global.set $p
+      i32.const 0
+      return
+    end
+    i32.const 0
+    i32.const 63
+    local.get $target
+    i32.const 0
+    i32.lt_s
+    select
+    local.tee $target
+    global.set $vp
+    local.get $target
+    global.set $p
+    i32.const 0
+  )
+
+  ;; 指针未越界且 p + min ..= p + max 都在纸带内
+  (func $fits (param $min i32) (param $max i32) (result i32)
+    global.get $vp
+    global.get $p
+    i32.eq
+    global.get $p
+    local.get $min
+    i32.add
+    i32.const 0
+    i32.ge_s
+    i32.and
+    global.get $p
+    local.get $max
+    i32.add
+    i32.const 64
+    i32.lt_s
+    i32.and
+  )
+
+  (func (export "run") (result i32)
+    (local $byte i32)
+    (local $count i32)
+    (local $address i32)
+    block $exit
+      global.get $p
+      global.get $p
+      i32.load8_u
+      i32.const 8
+      i32.add
+      i32.store8
+      i32.const 0
+      i32.const 1
+      call $fits
+      if
+        global.get $p
+        i32.load8_u
+        local.set $count
+        global.get $p
+        i32.const 1
+        i32.add
+        local.tee $address
+        local.get $address
+        i32.load8_u
+        local.get $count
+        i32.const 9
+        i32.mul
+        i32.add
+        i32.store8
+        global.get $p
+        i32.const 0
+        i32.store8
+      end
+      block $end2
+        loop $body2
+          global.get $p
+          i32.load8_u
+          i32.eqz
+          br_if $end2
+          i32.const 1
+          i32.const 9
+          call $shift
+          br_if $exit
+          global.get $p
+          global.get $p
+          i32.load8_u
+          i32.const 9
+          i32.add
+          i32.store8
+          i32.const -1
+          i32.const 19
+          call $shift
+          br_if $exit
+          global.get $p
+          global.get $p
+          i32.load8_u
+          i32.const -1
+          i32.add
+          i32.store8
+          br $body2
+        end
+      end
+      i32.const 1
+      i32.const 22
+      call $shift
+      br_if $exit
+      global.get $p
+      i32.load8_u
+      call $write_byte
+      i32.const 2
+      i32.const 24
+      call $shift
+      br_if $exit
+      global.get $p
+      global.get $p
+      i32.load8_u
+      i32.const 10
+      i32.add
+      i32.store8
+      i32.const -1
+      i32.const 0
+      call $fits
+      if
+        global.get $p
+        i32.load8_u
+        local.set $count
+        global.get $p
+        i32.const -1
+        i32.add
+        local.tee $address
+        local.get $address
+        i32.load8_u
+        local.get $count
+        i32.const 10
+        i32.mul
+        i32.add
+        i32.store8
+        global.get $p
+        i32.const 0
+        i32.store8
+      end
+      block $end13
+        loop $body13
+          global.get $p
+          i32.load8_u
+          i32.eqz
+          br_if $end13
+          i32.const -1
+          i32.const 37
+          call $shift
+          br_if $exit
+          global.get $p
+          global.get $p
+          i32.load8_u
+          i32.const 10
+          i32.add
+          i32.store8
+          i32.const 1
+          i32.const 48
+          call $shift
+          br_if $exit
+          global.get $p
+          global.get $p
+          i32.load8_u
+          i32.const -1
+          i32.add
+          i32.store8
+          br $body13
+        end
+      end
+      i32.const -1
+      i32.const 51
+      call $shift
+      br_if $exit
+      global.get $p
+      global.get $p
+      i32.load8_u
+      i32.const 1
+      i32.add
+      i32.store8
+      global.get $p
+      i32.load8_u
+      call $write_byte
+      global.get $p
+      global.get $p
+      i32.load8_u
+      i32.const 7
+      i32.add
+      i32.store8
+      global.get $p
+      i32.load8_u
+      call $write_byte
+      global.get $p
+      i32.load8_u
+      call $write_byte
+      global.get $p
+      global.get $p
+      i32.load8_u
+      i32.const 3
+      i32.add
+      i32.store8
+      global.get $p
+      i32.load8_u
+      call $write_byte
+      i32.const 2
+      i32.const 67
+      call $shift
+      br_if $exit
+      global.get $p
+      global.get $p
+      i32.load8_u
+      i32.const 4
+      i32.add
+      i32.store8
+      i32.const -1
+      i32.const 0
+      call $fits
+      if
+        global.get $p
+        i32.load8_u
+        local.set $count
+        global.get $p
+        i32.const -1
+        i32.add
+        local.tee $address
+        local.get $address
+        i32.load8_u
+        local.get $count
+        i32.const 11
+        i32.mul
+        i32.add
+        i32.store8
+        global.get $p
+        i32.const 0
+        i32.store8
+      end
+      block $end30
+        loop $body30
+          global.get $p
+          i32.load8_u
+          i32.eqz
+          br_if $end30
+          i32.const -1
+          i32.const 74
+          call $shift
+          br_if $exit
+          global.get $p
+          global.get $p
+          i32.load8_u
+          i32.const 11
+          i32.add
+          i32.store8
+          i32.const 1
+          i32.const 86
+          call $shift
+          br_if $exit
+          global.get $p
+          global.get $p
+          i32.load8_u
+          i32.const -1
+          i32.add
+          i32.store8
+          br $body30
+        end
+      end
+      i32.const -1
+      i32.const 89
+      call $shift
+      br_if $exit
+      global.get $p
+      i32.load8_u
+      call $write_byte
+      global.get $p
+      global.get $p
+      i32.load8_u
+      i32.const -12
+      i32.add
+      i32.store8
+      global.get $p
+      i32.load8_u
+      call $write_byte
+      i32.const -3
+      i32.const 104
+      call $shift
+      br_if $exit
+      global.get $p
+      global.get $p
+      i32.load8_u
+      i32.const 3
+      i32.add
+      i32.store8
+      i32.const 0
+      i32.const 1
+      call $fits
+      if
+        global.get $p
+        i32.load8_u
+        local.set $count
+        global.get $p
+        i32.const 1
+        i32.add
+        local.tee $address
+        local.get $address
+        i32.load8_u
+        local.get $count
+        i32.const 5
+        i32.mul
+        i32.add
+        i32.store8
+        global.get $p
+        i32.const 0
+        i32.store8
+      end
+      block $end43
+        loop $body43
+          global.get $p
+          i32.load8_u
+          i32.eqz
+          br_if $end43
+          i32.const 1
+          i32.const 111
+          call $shift
+          br_if $exit
+          global.get $p
+          global.get $p
+          i32.load8_u
+          i32.const 5
+          i32.add
+          i32.store8
+          i32.const -1
+          i32.const 117
+          call $shift
+          br_if $exit
+          global.get $p
+          global.get $p
+          i32.load8_u
+          i32.const -1
+          i32.add
+          i32.store8
+          br $body43
+        end
+      end
+      i32.const 1
+      i32.const 120
+      call $shift
+      br_if $exit
+      global.get $p
+      i32.load8_u
+      call $write_byte
+      i32.const 1
+      i32.const 122
+      call $shift
+      br_if $exit
+      global.get $p
+      i32.load8_u
+      call $write_byte
+      global.get $p
+      global.get $p
+      i32.load8_u
+      i32.const 3
+      i32.add
+      i32.store8
+      global.get $p
+      i32.load8_u
+      call $write_byte
+      global.get $p
+      global.get $p
+      i32.load8_u
+      i32.const -6
+      i32.add
+      i32.store8
+      global.get $p
+      i32.load8_u
+      call $write_byte
+      global.get $p
+      global.get $p
+      i32.load8_u
+      i32.const -8
+      i32.add
+      i32.store8
+      global.get $p
+      i32.load8_u
+      call $write_byte
+      i32.const 1
+      i32.const 144
+      call $shift
+      br_if $exit
+      global.get $p
+      global.get $p
+      i32.load8_u
+      i32.const 1
+      i32.add
+      i32.store8
+      global.get $p
+      i32.load8_u
+      call $write_byte
+i32.const 0
+      return
+    end
+    global.get $command
+    i32.const 1
+    i32.add
+  )
+)
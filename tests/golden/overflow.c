@@ -0,0 +1,57 @@
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#define LEN 8L
+
+static uint32_t tape[LEN];
+static long p = 0;  /* 实际读写的格子 */
+static long vp = 0; /* 指针的逻辑位置 */
+
+static void shift(long offset, long command) {
+    long target = vp + offset;
+    if (target < 0 || target >= LEN) {
+        /* 逐格走到越界的那一条指令 */
+        long steps = offset > 0 ? LEN - 1 - vp : vp;
+        fflush(stdout);
+        fprintf(stderr, "Overflow exit. (command %ld, pointer %ld)\n",
+                command + steps, offset > 0 ? LEN - 1 : 0L);
+        exit(4);
+    }
+    vp = p = target;
+}
+
+#define MAX 4294967295ULL
+static void add(long long delta, long command) {
+    unsigned long long value = tape[p];
+    unsigned long long amount = delta < 0 ? 0ULL - delta : (unsigned long long)delta;
+    unsigned long long room = delta < 0 ? value : MAX - value;
+    if (amount > room) {
+        /* 逐次加减到越界的那一条指令 */
+        fflush(stdout);
+        fprintf(stderr, "Cell overflow exit. (command %ld, pointer %ld)\n",
+                command + (long)room, vp);
+        exit(4);
+    }
+    tape[p] = delta < 0 ? value - amount : value + amount;
+}
+
+static int fits(long min, long max) {
+    return vp == p && p + min >= 0 && p + max < LEN;
+}
+
+int main(void) {
+    shift(1, 0);
+    add(8, 1);
+    while (tape[p]) {
+        shift(-1, 10);
+        add(9, 11);
+        shift(1, 20);
+        add(-1, 21);
+    }
+    shift(-2, 23);
+    add(-2, 25);
+    putchar(tape[p]);
+    fflush(stdout);
+    return 0;
+}
@@ -0,0 +1,151 @@
+(module
+  (import "env" "read_byte" (func $read_byte (result i32)))
+  (import "env" "write_byte" (func $write_byte (param i32)))
+  (memory (export "memory") 1)
+  (global $p (mut i32) (i32.const 0))
+  (global $vp (mut i32) (i32.const 0))
+  (global $command (mut i32) (i32.const 0))
+
+  ;; 移动指针，越界时按 Overflow 处理，返回非 0 表示需要退出
+  (func $shift (param $offset i32) (param $command i32) (result i32)
+    (local $target i32)
+    global.get $vp
+    local.get $offset
+    i32.add
+    local.set $target
+    local.get $target
+    i32.const 8
+    i32.lt_u
+    if
+      local.get $target
+      global.set $vp
+      local.get $target
+      global.set $p
+      i32.const 0
+      return
+    end
+    local.get $target
+    i32.const 8
+    i32.rem_s
+    local.tee $target
+    i32.const 0
+    i32.lt_s
+    if
+      local.get $target
+      i32.const 8
+      i32.add
+      local.set $target
+    end
+    local.get $target
+    global.set $vp
+    local.get $target
+    global.set $p
+    i32.const 0
+  )
+
+  ;; 指针未越界且 p + min ..= p + max 都在纸带内
+  (func $fits (param $min i32) (param $max i32) (result i32)
+    global.get $vp
+    global.get $p
+    i32.eq
+    global.get $p
+    local.get $min
+    i32.add
+    i32.const 0
+    i32.ge_s
+    i32.and
+    global.get $p
+    local.get $max
+    i32.add
+    i32.const 8
+    i32.lt_s
+    i32.and
+  )
+
+  (func (export "run") (result i32)
+    (local $byte i32)
+    (local $count i32)
+    (local $address i32)
+    block $exit
+      i32.const 1
+      i32.const 0
+      call $shift
+      br_if $exit
+      global.get $p
+      global.get $p
+      i32.load8_u
+      i32.const 8
+      i32.add
+      i32.store8
+      i32.const -1
+      i32.const 0
+      call $fits
+      if
+        global.get $p
+        i32.load8_u
+        local.set $count
+        global.get $p
+        i32.const -1
+        i32.add
+        local.tee $address
+        local.get $address
+        i32.load8_u
+        local.get $count
+        i32.const 9
+        i32.mul
+        i32.add
+        i32.store8
+        global.get $p
+        i32.const 0
+        i32.store8
+      end
+      block $end3
+        loop $body3
+          global.get $p
+          i32.load8_u
+          i32.eqz
+          br_if $end3
+          i32.const -1
+          i32.const 10
+          call $shift
+          br_if $exit
+          global.get $p
+          global.get $p
+          i32.load8_u
+          i32.const 9
+          i32.add
+          i32.store8
+          i32.const 1
+          i32.const 20
+          call $shift
+          br_if $exit
+          global.get $p
+          global.get $p
+          i32.load8_u
+          i32.const -1
+          i32.add
+          i32.store8
+          br $body3
+        end
+      end
+      i32.const -2
+      i32.const 23
+      call $shift
+      br_if $exit
+      global.get $p
+      global.get $p
+      i32.load8_u
+      i32.const -2
+      i32.add
+      i32.store8
+      global.get $p
+      i32.load8_u
+      call $write_byte
+i32.const 0
+      return
+    end
+    global.get $command
+    i32.const 1
+    i32.add
+  )
+)
@@ -0,0 +1,48 @@
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#define LEN 16L
+
+static uint64_t tape[LEN];
+static long p = 0;  /* 实际读写的格子 */
+static long vp = 0; /* 指针的逻辑位置 */
+
+static void shift(long offset, long command) {
+    long target = vp + offset;
+    if (target < 0 || target >= LEN) {
+        /* 逐格走到越界的那一条指令 */
+        long steps = offset > 0 ? LEN - 1 - vp : vp;
+        fflush(stdout);
+        fprintf(stderr, "Overflow exit. (command %ld, pointer %ld)\n",
+                command + steps, offset > 0 ? LEN - 1 : 0L);
+        exit(4);
+    }
+    vp = p = target;
+}
+
+#define MAX 18446744073709551615ULL
+
+static int fits(long min, long max) {
+    return vp == p && p + min >= 0 && p + max < LEN;
+}
+
+int main(void) {
+    shift(1, 0);
+    tape[p] += 8;
+    if (fits(-1, 0)) {
+        tape[p - 1] += tape[p] * 9ULL;
+        tape[p] = 0;
+    }
+    while (tape[p]) {
+        shift(-1, 10);
+        tape[p] += 9;
+        shift(1, 20);
+        tape[p] -= 1;
+    }
+    shift(-2, 23);
+    tape[p] -= 2;
+    putchar(tape[p]);
+    fflush(stdout);
+    return 0;
+}
@@ -0,0 +1,220 @@
+.intel_syntax noprefix
+    .globl _start
+
+    .bss
+tape:
+    .zero 128
+outbuf:
+    .zero 4096
+numbuf:
+    .zero 32
+
+    .section .rodata
+overflow_message:
+    .ascii "Overflow exit. (command "
+cell_message:
+    .ascii "Cell overflow exit. (command "
+minus_message:
+    .ascii "-"
+pointer_message:
+    .ascii ", pointer "
+end_message:
+    .ascii ")\n"
+input_message:
+    .ascii "Failed to read input.\n"
+output_message:
+    .ascii "Failed to write output.\n"
+
+    .text
+_start:
+    lea rbx, [rip + tape]
+    xor r12d, r12d
+    xor r13d, r13d
+    xor r14d, r14d
+lea rax, [r13 + 1]
+    cmp rax, 16
+    jae .L0_slow
+    mov r13, rax
+    mov r12, rax
+    jmp .L0_done
+.L0_slow:
+    mov rsi, 0
+    call shift
+.L0_done:
+    add qword ptr [rbx + r12*8], 8
+cmp r13, r12
+    jne .L2_done
+    lea rax, [r12 - 1]
+    test rax, rax
+    js .L2_done
+    lea rax, [r12 + 0]
+    cmp rax, 16
+    jge .L2_done
+    mov rax, qword ptr [rbx + r12*8]
+    imul rcx, rax, 9
+    add qword ptr [rbx + r12*8 - 8], rcx
+    mov qword ptr [rbx + r12*8], 0
+.L2_done:
+    cmp qword ptr [rbx + r12*8], 0
+    je .L8_end
+.L3_body:
+lea rax, [r13 - 1]
+    cmp rax, 16
+    jae .L4_slow
+    mov r13, rax
+    mov r12, rax
+    jmp .L4_done
+.L4_slow:
+    mov rsi, 10
+    call shift
+.L4_done:
+    add qword ptr [rbx + r12*8], 9
+lea rax, [r13 + 1]
+    cmp rax, 16
+    jae .L6_slow
+    mov r13, rax
+    mov r12, rax
+    jmp .L6_done
+.L6_slow:
+    mov rsi, 20
+    call shift
+.L6_done:
+    sub qword ptr [rbx + r12*8], 1
+    cmp qword ptr [rbx + r12*8], 0
+    jne .L3_body
+.L8_end:
+lea rax, [r13 - 2]
+    cmp rax, 16
+    jae .L9_slow
+    mov r13, rax
+    mov r12, rax
+    jmp .L9_done
+.L9_slow:
+    mov rsi, 23
+    call shift
+.L9_done:
+    sub qword ptr [rbx + r12*8], 2
+    mov rax, qword ptr [rbx + r12*8]
+    call put_byte
+call flush
+    mov eax, 60
+    xor edi, edi
+    syscall
+
+shift:
+mov rcx, 15
+    sub rcx, r13
+    mov rdx, r13
+    xor r8d, r8d
+    cmp rax, r13
+    cmovg rdx, rcx
+    mov rcx, 15
+    cmovg r8, rcx
+    add rsi, rdx
+    push r8
+    push rsi
+    call flush
+    lea rsi, [rip + overflow_message]
+    mov edx, 24
+    call error_write
+    pop rax
+    call error_int
+    lea rsi, [rip + pointer_message]
+    mov edx, 10
+    call error_write
+    pop rax
+    call error_int
+    lea rsi, [rip + end_message]
+    mov edx, 2
+    call error_write
+    mov eax, 60
+    mov edi, 4
+    syscall
+
+put_byte:
+    lea rcx, [rip + outbuf]
+    mov byte ptr [rcx + r14], al
+    inc r14
+    cmp r14, 4096
+    jae flush
+    ret
+
+put_int:
+    call format_int
+.Lput_digit:
+    mov al, byte ptr [rsi]
+    push rsi
+    push rdx
+    call put_byte
+    pop rdx
+    pop rsi
+    inc rsi
+    dec rdx
+    jnz .Lput_digit
+    mov al, 32
+    jmp put_byte
+
+# 把 rax 转成十进制放进 numbuf，返回 rsi 为首地址，rdx 为长度
+format_int:
+    lea rsi, [rip + numbuf + 32]
+    mov rcx, 10
+.Lformat_digit:
+    xor edx, edx
+    div rcx
+    add dl, 48
+    dec rsi
+    mov byte ptr [rsi], dl
+    test rax, rax
+    jnz .Lformat_digit
+    lea rdx, [rip + numbuf + 32]
+    sub rdx, rsi
+    ret
+
+flush:
+    xor esi, esi
+.Lflush_more:
+    cmp rsi, r14
+    jae .Lflush_done
+    push rsi
+    mov eax, 1
+    mov edi, 1
+    lea rcx, [rip + outbuf]
+    mov rdx, r14
+    sub rdx, rsi
+    add rsi, rcx
+    syscall
+    pop rsi
+    test rax, rax
+    js output_error
+    add rsi, rax
+    jmp .Lflush_more
+.Lflush_done:
+    xor r14d, r14d
+    ret
+
+error_write:
+    mov eax, 1
+    mov edi, 2
+    syscall
+    ret
+
+error_int:
+    call format_int
+    jmp error_write
+
+input_error:
+    call flush
+    lea rsi, [rip + input_message]
+    mov edx, 22
+    jmp error_exit
+
+output_error:
+    xor r14d, r14d
+    lea rsi, [rip + output_message]
+    mov edx, 24
+
+error_exit:
+    call error_write
+    mov eax, 60
+    mov edi, 4
+    syscall
@@ -0,0 +1,47 @@
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#define LEN 64L
+
+static uint16_t tape[LEN];
+static long p = 0;  /* 实际读写的格子 */
+static long vp = 0; /* 指针的逻辑位置 */
+
+static void shift(long offset, long command) {
+    (void)command;
+    vp += offset;
+    if (vp < 0) vp = 0;
+    if (vp >= LEN) vp = LEN - 1;
+    p = vp;
+}
+
+#define MAX 65535ULL
+static void add(long long delta, long command) {
+    unsigned long long value = tape[p];
+    unsigned long long amount = delta < 0 ? 0ULL - delta : (unsigned long long)delta;
+    (void)command;
+    if (delta < 0)
+        tape[p] = amount > value ? 0 : value - amount;
+    else
+        tape[p] = amount > MAX - value ? MAX : value + amount;
+}
+
+static int fits(long min, long max) {
+    return vp == p && p + min >= 0 && p + max < LEN;
+}
+
+int main(void) {
+    add(10, 0);
+    while (tape[p]) {
+        shift(1, 11);
+        fflush(stdout);
+        { int c = getchar(); tape[p] = c == EOF ? 0 : c; }
+        add(1, 13);
+        printf("%llu ", (unsigned long long)tape[p]);
+        shift(-1, 15);
+        add(-1, 16);
+    }
+    fflush(stdout);
+    return 0;
+}
@@ -0,0 +1,214 @@
+.intel_syntax noprefix
+    .globl _start
+
+    .bss
+tape:
+    .zero 128
+outbuf:
+    .zero 4096
+numbuf:
+    .zero 32
+
+    .section .rodata
+overflow_message:
+    .ascii "Overflow exit. (command "
+cell_message:
+    .ascii "Cell overflow exit. (command "
+minus_message:
+    .ascii "-"
+pointer_message:
+    .ascii ", pointer "
+end_message:
+    .ascii ")\n"
+input_message:
+    .ascii "Failed to read input.\n"
+output_message:
+    .ascii "Failed to write output.\n"
+
+    .text
+_start:
+    lea rbx, [rip + tape]
+    xor r12d, r12d
+    xor r13d, r13d
+    xor r14d, r14d
+    mov rax, 10
+    mov rsi, 0
+    call add_cell
+    cmp word ptr [rbx + r12*2], 0
+    je .L8_end
+.L1_body:
+lea rax, [r13 + 1]
+    cmp rax, 64
+    jae .L2_slow
+    mov r13, rax
+    mov r12, rax
+    jmp .L2_done
+.L2_slow:
+    mov rsi, 11
+    call shift
+.L2_done:
+call flush
+    xor eax, eax
+    xor edi, edi
+    lea rsi, [rip + numbuf]
+    mov edx, 1
+    syscall
+    test rax, rax
+    js input_error
+    movzx eax, byte ptr [rip + numbuf]
+    jnz .L3_read
+    mov rax, 0
+.L3_read:
+    mov word ptr [rbx + r12*2], ax
+.L3_eof:
+    mov rax, 1
+    mov rsi, 13
+    call add_cell
+    movzx eax, word ptr [rbx + r12*2]
+    call put_int
+lea rax, [r13 - 1]
+    cmp rax, 64
+    jae .L6_slow
+    mov r13, rax
+    mov r12, rax
+    jmp .L6_done
+.L6_slow:
+    mov rsi, 15
+    call shift
+.L6_done:
+    mov rax, -1
+    mov rsi, 16
+    call add_cell
+    cmp word ptr [rbx + r12*2], 0
+    jne .L1_body
+.L8_end:
+call flush
+    mov eax, 60
+    xor edi, edi
+    syscall
+
+shift:
+xor ecx, ecx
+    test rax, rax
+    cmovs rax, rcx
+    mov rcx, 63
+    cmp rax, rcx
+    cmovg rax, rcx
+    mov r13, rax
+    mov r12, rax
+    ret
+
+add_cell:
+    mov rdx, rax
+    movzx eax, word ptr [rbx + r12*2]
+    test rdx, rdx
+    js .Ladd_down
+    mov rcx, 65535
+    sub rcx, rax
+    cmp rdx, rcx
+    ja .Ladd_over
+    add rax, rdx
+    jmp .Ladd_store
+.Ladd_down:
+    mov rcx, rdx
+    neg rcx
+    cmp rcx, rax
+    ja .Ladd_under
+    sub rax, rcx
+.Ladd_store:
+    mov word ptr [rbx + r12*2], ax
+    ret
+.Ladd_over:
+    mov rax, 65535
+    jmp .Ladd_store
+.Ladd_under:
+    xor eax, eax
+    jmp .Ladd_store
+
+put_byte:
+    lea rcx, [rip + outbuf]
+    mov byte ptr [rcx + r14], al
+    inc r14
+    cmp r14, 4096
+    jae flush
+    ret
+
+put_int:
+    call format_int
+.Lput_digit:
+    mov al, byte ptr [rsi]
+    push rsi
+    push rdx
+    call put_byte
+    pop rdx
+    pop rsi
+    inc rsi
+    dec rdx
+    jnz .Lput_digit
+    mov al, 32
+    jmp put_byte
+
+# 把 rax 转成十进制放进 numbuf，返回 rsi 为首地址，rdx 为长度
+format_int:
+    lea rsi, [rip + numbuf + 32]
+    mov rcx, 10
+.Lformat_digit:
+    xor edx, edx
+    div rcx
+    add dl, 48
+    dec rsi
+    mov byte ptr [rsi], dl
+    test rax, rax
+    jnz .Lformat_digit
+    lea rdx, [rip + numbuf + 32]
+    sub rdx, rsi
+    ret
+
+flush:
+    xor esi, esi
+.Lflush_more:
+    cmp rsi, r14
+    jae .Lflush_done
+    push rsi
+    mov eax, 1
+    mov edi, 1
+    lea rcx, [rip + outbuf]
+    mov rdx, r14
+    sub rdx, rsi
+    add rsi, rcx
+    syscall
+    pop rsi
+    test rax, rax
+    js output_error
+    add rsi, rax
+    jmp .Lflush_more
+.Lflush_done:
+    xor r14d, r14d
+    ret
+
+error_write:
+    mov eax, 1
+    mov edi, 2
+    syscall
+    ret
+
+error_int:
+    call format_int
+    jmp error_write
+
+input_error:
+    call flush
+    lea rsi, [rip + input_message]
+    mov edx, 22
+    jmp error_exit
+
+output_error:
+    xor r14d, r14d
+    lea rsi, [rip + output_message]
+    mov edx, 24
+
+error_exit:
+    call error_write
+    mov eax, 60
+    mov edi, 4
+    syscall
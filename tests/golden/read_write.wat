@@ -0,0 +1,140 @@
+(module
+  (import "env" "read_byte" (func $read_byte (result i32)))
+  (import "env" "write_byte" (func $write_byte (param i32)))
+  (memory (export "memory") 1)
+  (global $p (mut i32) (i32.const 0))
+  (global $vp (mut i32) (i32.const 0))
+  (global $command (mut i32) (i32.const 0))
+
+  ;; 移动指针，越界时按 Overflow 处理，返回非 0 表示需要退出
+  (func $shift (param $offset i32) (param $command i32) (result i32)
+    (local $target i32)
+    global.get $vp
+    local.get $offset
+    i32.add
+    local.set $target
+    local.get $target
+    i32.const 64
+    i32.lt_u
+    if
+      local.get $target
+      global.set $vp
+      local.get $target
+      global.set $p
+      i32.const 0
+      return
+    end
+    local.get $command
+    i32.const 63
+    global.get $vp
+    i32.sub
+    global.get $vp
+    local.get $offset
+    i32.const 0
+    i32.gt_s
+    select
+    i32.add
+    global.set $command
+    i32.const 1
+    return
+  )
+
+  ;; 指针未越界且 p + min ..= p + max 都在纸带内
+  (func $fits (param $min i32) (param $max i32) (result i32)
+    global.get $vp
+    global.get $p
+    i32.eq
+    global.get $p
+    local.get $min
+    i32.add
+    i32.const 0
+    i32.ge_s
+    i32.and
+    global.get $p
+    local.get $max
+    i32.add
+    i32.const 64
+    i32.lt_s
+    i32.and
+  )
+
+  (func $put_digits (param $value i32)
+    local.get $value
+    i32.const 10
+    i32.ge_u
+    if
+      local.get $value
+      i32.const 10
+      i32.div_u
+      call $put_digits
+    end
+    local.get $value
+    i32.const 10
+    i32.rem_u
+    i32.const 48
+    i32.add
+    call $write_byte
+  )
+
+  (func (export "run") (result i32)
+    (local $byte i32)
+    (local $count i32)
+    (local $address i32)
+    block $exit
+      global.get $p
+      global.get $p
+      i32.load8_u
+      i32.const 10
+      i32.add
+      i32.store8
+      block $end1
+        loop $body1
+          global.get $p
+          i32.load8_u
+          i32.eqz
+          br_if $end1
+          i32.const 1
+          i32.const 11
+          call $shift
+          br_if $exit
+          call $read_byte
+          local.tee $byte
+          i32.const 0
+          i32.ge_s
+          if
+            global.get $p
+            local.get $byte
+            i32.store8
+          end
+          global.get $p
+          global.get $p
+          i32.load8_u
+          i32.const 1
+          i32.add
+          i32.store8
+          global.get $p
+          i32.load8_u
+          call $put_digits
+          i32.const 32
+          call $write_byte
+          i32.const -1
+          i32.const 15
+          call $shift
+          br_if $exit
+          global.get $p
+          global.get $p
+          i32.load8_u
+          i32.const -1
+          i32.add
+          i32.store8
+          br $body1
+        end
+      end
+i32.const 0
+      return
+    end
+    global.get $command
+    i32.const 1
+    i32.add
+  )
+)
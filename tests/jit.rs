@@ -0,0 +1,116 @@
+#![cfg(all(feature = "jit", target_arch = "x86_64", target_os = "linux"))]
+
+use std::{collections::VecDeque, fs, num::NonZeroUsize};
+
+use brain_fxxker::{jit::Jit, testing, CellBits, CellOverflow, Eof, Ir, Options, Overflow, Program};
+
+const OVERFLOWS: [Overflow; 4] = [Overflow::Block, Overflow::Overflow, Overflow::Loop, Overflow::Exit];
+
+/// 分别用 JIT 和 `Ir::run` 执行，比较输出、最终的纸带和错误
+fn compare(source: &str, options: Options, input: &[u8]) {
+    let ir = Ir::new(Program::from(source).unwrap());
+    let jit = Jit::compile(&ir, options).expect("the options are supported by the JIT");
+    let mut expected_output = Vec::new();
+    let expected = ir.run(options, &mut VecDeque::from(input.to_vec()), &mut expected_output);
+    let mut output = Vec::new();
+    let actual = jit.run(&mut VecDeque::from(input.to_vec()), &mut output);
+    assert_eq!(output, expected_output, "{source}");
+    match (actual, expected) {
+        (Ok(actual), Ok(expected)) => {
+            assert_eq!(actual.cells(), expected.cells(), "{source}");
+            assert_eq!(actual.pointer(), expected.pointer(), "{source}");
+            assert_eq!(actual.virtual_pointer(), expected.virtual_pointer(), "{source}");
+        },
+        (Err(actual), Err(expected)) => assert_eq!(actual.to_string(), expected.to_string(), "{source}"),
+        (actual, expected) => panic!(
+            "{source}: the JIT returned {:?} but the interpreter returned {:?}",
+            actual.map(|_| ()),
+            expected.map(|_| ())
+        ),
+    }
+}
+
+fn compare_if_ends(source: &str, options: Options) {
+    if testing::halts(source, options) {
+        compare(source, options, &[]);
+    }
+}
+
+fn options(overflow: Overflow, tape_length: usize) -> Options {
+    let tape_length = NonZeroUsize::new(tape_length).unwrap();
+    Options { overflow, tape_length, ..Options::default() }
+}
+
+#[test]
+fn examples() {
+    for path in ["examples/hello_world.bf", "examples/hello_world_ksabry.bf", "examples/overflow.bf"] {
+        let source = fs::read_to_string(path).unwrap();
+        for overflow in OVERFLOWS {
+            compare_if_ends(&source, options(overflow, 30000));
+            compare_if_ends(&source, options(overflow, 8));
+        }
+    }
+}
+
+#[test]
+fn read_write() {
+    let source = fs::read_to_string("examples/read_write.bf").unwrap();
+    for eof in [Eof::Unchanged, Eof::Zero, Eof::Max] {
+        for output_as_int in [false, true] {
+            let options = Options { eof, output_as_int, ..Options::default() };
+            compare(&source, options, b"abcdefghij");
+            compare(&source, options, b"abc");
+        }
+    }
+}
+
+#[test]
+fn every_overflow_policy() {
+    let sources = ["<<+>>>>>+.<.", ">>>>>>>>+<<<<<<<<<<-.>.", "+[<+]", "+[>+]", "<<<<<<<<<<<<.", "+>+<<<<[-]>>>>>>>>."];
+    for overflow in OVERFLOWS {
+        for source in sources {
+            compare_if_ends(source, options(overflow, 4));
+        }
+    }
+}
+
+#[test]
+fn exit_at_both_ends() {
+    let ir = Ir::new(Program::from("+>>\n>>").unwrap());
+    let jit = Jit::compile(&ir, options(Overflow::Exit, 4)).unwrap();
+    let err = jit.run(&mut VecDeque::new(), &mut Vec::new()).err().unwrap();
+    assert_eq!((err.program_counter, err.pointer), (4, 3));
+    assert_eq!((err.location.line, err.location.column), (2, 2));
+
+    let ir = Ir::new(Program::from(">+<<").unwrap());
+    let jit = Jit::compile(&ir, options(Overflow::Exit, 4)).unwrap();
+    let err = jit.run(&mut VecDeque::new(), &mut Vec::new()).err().unwrap();
+    assert_eq!((err.program_counter, err.pointer), (3, 0));
+
+    for source in ["+>>\n>>", ">+<<", "+[>+]", "+[<+]", "+++[->+<<]", ">>>+[>]", "+[<<]", ">>>+[->+<]"] {
+        compare(source, options(Overflow::Exit, 4), &[]);
+    }
+}
+
+#[test]
+fn fallback_near_the_edges() {
+    for overflow in OVERFLOWS {
+        for source in testing::edge_programs() {
+            compare_if_ends(&source, options(overflow, 4));
+        }
+    }
+}
+
+#[test]
+fn unsupported_options() {
+    let ir = Ir::new(Program::from("+.").unwrap());
+    let unsupported = [
+        Options { cell_bits: CellBits::B16, ..Options::default() },
+        Options { cell_overflow: CellOverflow::Saturate, ..Options::default() },
+        Options { cell_overflow: CellOverflow::Exit, ..Options::default() },
+        options(Overflow::Grow, 1),
+    ];
+    for options in unsupported {
+        assert!(Jit::compile(&ir, options).is_none());
+    }
+}
@@ -0,0 +1,69 @@
+use std::{fs, num::NonZeroUsize};
+
+use brain_fxxker::{wat, CellBits, CellOverflow, Ir, Options, Overflow, Program};
+
+/// 汇编成二进制模块并做完整的类型检查，出错时带上出错的文本
+fn validate(text: &str, name: &str) {
+    let binary = ::wat::parse_str(text).unwrap_or_else(|err| panic!("{name}: {err}"));
+    if let Err(err) = wasmparser::Validator::new().validate_all(&binary) {
+        panic!("{name}: {err}");
+    }
+}
+
+fn compile(path: &str, options: Options) -> String {
+    let source = fs::read_to_string(path).unwrap();
+    wat::compile(&Ir::new(Program::from(&source).unwrap()), options).unwrap()
+}
+
+#[test]
+fn hello_world() {
+    let wat = compile("examples/hello_world.bf", Options::default());
+    assert_eq!(wat, include_str!("golden/hello_world.wat"));
+}
+
+#[test]
+fn read_write_with_exit_and_int_output() {
+    let options = Options {
+        overflow: Overflow::Exit,
+        output_as_int: true,
+        ..Options::default()
+    };
+    let wat = compile("examples/read_write.bf", options);
+    assert_eq!(wat, include_str!("golden/read_write.wat"));
+}
+
+#[test]
+fn overflow_with_loop() {
+    let options = Options {
+        overflow: Overflow::Loop,
+        tape_length: NonZeroUsize::new(8).unwrap(),
+        ..Options::default()
+    };
+    let wat = compile("examples/overflow.bf", options);
+    assert_eq!(wat, include_str!("golden/overflow.wat"));
+}
+
+#[test]
+fn golden_files_are_valid() {
+    for entry in fs::read_dir("tests/golden").unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_some_and(|extension| extension == "wat") {
+            validate(&fs::read_to_string(&path).unwrap(), &path.display().to_string());
+        }
+    }
+}
+
+#[test]
+fn every_option_is_valid() {
+    let overflows = [Overflow::Block, Overflow::Overflow, Overflow::Loop, Overflow::Exit, Overflow::Grow];
+    for path in ["examples/hello_world.bf", "examples/read_write.bf"] {
+        for cell_bits in [CellBits::B8, CellBits::B16, CellBits::B32, CellBits::B64] {
+            for cell_overflow in [CellOverflow::Wrap, CellOverflow::Saturate, CellOverflow::Exit] {
+                for overflow in overflows {
+                    let options = Options { cell_bits, cell_overflow, overflow, ..Options::default() };
+                    validate(&compile(path, options), path);
+                }
+            }
+        }
+    }
+}